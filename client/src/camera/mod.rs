@@ -2,8 +2,10 @@ use bevy::prelude::*;
 use bevy::render::camera::ScalingMode;
 use bevy::transform::TransformSystem;
 use leafwing_input_manager::prelude::ActionState;
-use lightyear::prelude::client::{InterpolationSet, Predicted};
-use shared::network::protocol::prelude::TailPoints;
+use lightyear::prelude::client::InterpolationSet;
+use shared::map::{MAP_SIZE, MapMarker, MapSize};
+use shared::network::protocol::prelude::{TailLength, TailPoints};
+use crate::focus::FocusTarget;
 use crate::inputs::LocalInput;
 use crate::network::inputs::Owned;
 
@@ -52,26 +54,45 @@ fn toggle_camera(
     }
 }
 
-/// TODO: this kill thing is too complicated, set HasFocusHead component on the player
-/// entity once and then multiple systems can depend on HasFocusHead (follow camera, sounds, scope, etc.)
-/// also server can use HasFocusHead.
-///
-/// System to make the camera follow the head of the player, or the head of the killer
+/// Rate (per second) at which the camera catches up with its target: after `1 / CAMERA_DAMPING` seconds,
+/// about 63% of the distance has been covered, whatever the framerate
+const CAMERA_DAMPING: f32 = 6.0;
+/// How much the camera zooms out for each unit of tail length
+const ZOOM_PER_LENGTH: f32 = 0.002;
+const MAX_FOLLOW_ZOOM: f32 = 3.0;
+/// Empty space kept around the map in the full view, as a fraction of the map size
+const FULL_VIEW_MARGIN: f32 = 0.05;
+
+/// Fraction of the remaining distance to the target that the camera should cover during `delta_seconds`.
+/// Applying it twice with `dt / 2` gives the same result as applying it once with `dt`, so the movement
+/// doesn't depend on the framerate
+fn damping_factor(delta_seconds: f32) -> f32 {
+    1.0 - (-CAMERA_DAMPING * delta_seconds).exp()
+}
+
+/// Zoom (projection scale) of the follow camera for a snake of the given length
+fn follow_zoom(length: &TailLength) -> f32 {
+    (1.0 + length.current_size * ZOOM_PER_LENGTH).min(MAX_FOLLOW_ZOOM)
+}
+
+/// System to make the camera follow the head of the snake that the player is focused on
+/// (our own snake, the killer's snake after we died, or the snake that we are spectating)
 fn follow_camera(
-    predicted: Query<&TailPoints, With<Predicted>>,
-    mut camera_query: Query<&mut Transform, With<Camera>>,
+    time: Res<Time>,
+    player: Query<&FocusTarget, With<Owned>>,
+    snakes: Query<(&TailPoints, &TailLength)>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
 ) {
-    // how much we stick to the new position
-    // let lerp = 0.1;
-    // let lerp = 1.0;
-    if let Ok(mut camera_pos) = camera_query.get_single_mut() {
-        if let Ok(pos) = predicted.get_single() {
-            let head = pos.front().0;
-            // *camera_pos = Transform::from_translation(camera_pos.translation.mul_add(Vec3::splat(1.0 - lerp), Vec3::from((head, 0.0)) * lerp));
-            *camera_pos = Transform::from_xyz(head.x, head.y, 0.0);
-        }
-    }
-    // player is dead: camera follows killer's head
+    let Some((tail, length)) = player.get_single().ok().and_then(|focus| snakes.get(focus.0).ok()) else {
+        return;
+    };
+    let Ok((mut transform, mut projection)) = camera_query.get_single_mut() else {
+        return;
+    };
+    let factor = damping_factor(time.delta_seconds());
+    let head = tail.front().0.extend(transform.translation.z);
+    transform.translation = transform.translation.lerp(head, factor);
+    projection.scale += (follow_zoom(length) - projection.scale) * factor;
 }
 
 
@@ -84,12 +105,34 @@ fn enter_follow_camera(mut camera_query: Query<&mut OrthographicProjection, With
     }
 }
 
-/// Switch camera to full view, reset the projection
-fn enter_full_camera(mut camera_query: Query<&mut OrthographicProjection, With<Camera>>) {
-    if let Ok(mut projection) = camera_query.get_single_mut() {
-        projection.scaling_mode = ScalingMode::WindowSize(1.0);
+/// Switch camera to full view: center the camera on the map, and fit the whole map in the window.
+/// `AutoMin` keeps the aspect ratio of the window, and makes sure that both the width and the height of the map are visible
+fn enter_full_camera(
+    map: Query<&MapSize, With<MapMarker>>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+) {
+    let (width, height) = map.get_single().map_or((MAP_SIZE, MAP_SIZE), |size| (size.width, size.height));
+    if let Ok((mut transform, mut projection)) = camera_query.get_single_mut() {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+        projection.scaling_mode = ScalingMode::AutoMin {
+            min_width: width * (1.0 + FULL_VIEW_MARGIN),
+            min_height: height * (1.0 + FULL_VIEW_MARGIN),
+        };
         projection.scale = 1.0;
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    #[test]
+    fn test_damping_is_framerate_independent() {
+        let one_step = damping_factor(1.0 / 30.0);
+        let half_step = damping_factor(1.0 / 60.0);
+        // remaining distance after one step of 1/30s, or after two steps of 1/60s
+        let two_steps = 1.0 - (1.0 - half_step) * (1.0 - half_step);
+        assert!((one_step - two_steps).abs() < 1e-6);
+    }
+}
@@ -6,15 +6,16 @@ use bevy::app::{App, Plugin};
 use bevy::prelude::*;
 use lightyear::client::input_leafwing::ToggleActions;
 use lightyear::client::events::MessageEvent;
+use lightyear::client::interpolation::Interpolated;
 use lightyear::client::prediction::Predicted;
 use shared::network::protocol::{DeadGameAction, PlayerMovement};
-use shared::network::protocol::prelude::{HasPlayer, SnakeCollision};
-use crate::network::inputs::Owned;
+use shared::network::protocol::prelude::{HasPlayer, Player, SnakeCollision, TailLength};
+use crate::network::inputs::{Owned, SPAWN_KEY};
 
 pub(crate) struct DeathPlugin;
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States, Reflect)]
-enum GameState {
+pub(crate) enum GameState {
     Dead,
     #[default]
     Alive,
@@ -25,55 +26,121 @@ impl Plugin for DeathPlugin {
 
         // states
         app.init_state::<GameState>();
+        // resources
+        app.init_resource::<DeathInfo>();
+        app.init_resource::<LastLength>();
 
         // systems
         // TODO: toggling the actions is not enough, ideally we would disable/enable the entire input plugin
         // dead
-        app.add_systems(OnEnter(GameState::Dead), enable_dead_actions);
+        app.add_systems(OnEnter(GameState::Dead), (enable_dead_actions, show_death_screen));
+        app.add_systems(OnExit(GameState::Dead), hide_death_screen);
         app.add_systems(Update, set_alive_state.run_if(in_state(GameState::Dead)));
 
         // alive
         app.add_systems(OnEnter(GameState::Alive), enable_alive_actions);
 
         // all
-        app.add_systems(Update, handle_death_message);
+        app.add_systems(Update, (track_own_length, handle_death_message).chain());
 
         // reflect
         app.register_type::<GameState>();
     }
 }
 
+/// Information shown on the death screen
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+struct DeathInfo {
+    /// name of the killer, or None if we crashed into our own tail
+    killer: Option<String>,
+    length: f32,
+    /// 1 for the longest snake
+    rank: usize,
+}
+
+/// Length of our snake during the last frame, so that we still know it after the snake is despawned
+#[derive(Resource, Debug, Default)]
+struct LastLength(f32);
+
+#[derive(Component)]
+struct DeathScreen;
+
+fn track_own_length(
+    mut last_length: ResMut<LastLength>,
+    my_snake: Query<&TailLength, With<Predicted>>,
+) {
+    if let Ok(length) = my_snake.get_single() {
+        last_length.0 = length.current_size;
+    }
+}
+
 // 1. if it's our own death, enter death state
 // 2. if it's someone else's death, play death animation
 fn handle_death_message(
     mut next_state: ResMut<NextState<GameState>>,
+    mut death_info: ResMut<DeathInfo>,
+    last_length: Res<LastLength>,
     mut messages: EventReader<MessageEvent<SnakeCollision>>,
     player: Query<Entity, With<Owned>>,
+    players: Query<&Player>,
+    other_snakes: Query<&TailLength, With<Interpolated>>,
 ) {
+    let Ok(player_entity) = player.get_single() else {
+        return;
+    };
     for message in messages.read() {
         let message = message.message();
         trace!(?message, "Received death message");
-        if message.killed == player.single() {
+        if message.killed == player_entity {
             debug!("I died");
+            *death_info = DeathInfo {
+                killer: (message.killer != player_entity)
+                    .then(|| players.get(message.killer).ok())
+                    .flatten()
+                    .map(|killer| killer.name.clone()),
+                length: last_length.0,
+                rank: 1 + other_snakes.iter().filter(|length| length.current_size > last_length.0).count(),
+            };
             next_state.set(GameState::Dead);
         }
     }
 }
 
 // During dead state, show the death screen to the user
-fn show_death_screen(mut commands: Commands) {
-    // commands.spawn(NodeBundle {
-    //     style: Style {
-    //         display: Display::Flex,
-    //         position_type: PositionType::Absolute,
-    //         flex_direction: FlexDirection::Column,
-    //         justify_content: JustifyContent::Center,
-    //         align_items: AlignItems::Center,
-    //         ..Default::default()
-    //     },
-    //     ..Default::default()
-    // })
-    // })
+fn show_death_screen(mut commands: Commands, death_info: Res<DeathInfo>) {
+    let text = |value: String, font_size: f32| TextBundle::from_section(value, TextStyle {
+        font_size,
+        ..default()
+    });
+    let cause = match &death_info.killer {
+        Some(killer) => format!("Killed by {killer}"),
+        None => "You crashed".to_string(),
+    };
+    commands.spawn((NodeBundle {
+        style: Style {
+            display: Display::Flex,
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(10.0),
+            ..default()
+        },
+        background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+        ..default()
+    }, DeathScreen)).with_children(|parent| {
+        parent.spawn(text(cause, 48.0));
+        parent.spawn(text(format!("Length: {:.0}    Rank: #{}", death_info.length, death_info.rank), 24.0));
+        parent.spawn(text(format!("Press {:?} to respawn", SPAWN_KEY), 24.0));
+    });
+}
+
+fn hide_death_screen(mut commands: Commands, screen: Query<Entity, With<DeathScreen>>) {
+    for entity in screen.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
 }
 
 // 1. press spawn, send message to server
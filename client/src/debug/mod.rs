@@ -1,10 +1,17 @@
 use bevy::app::{App, Plugin};
-// use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
-pub struct DebugPlugin;
+mod overlay;
+
+pub struct DebugPlugin {
+    pub inspector: bool,
+}
 
 impl Plugin for DebugPlugin {
     fn build(&self, app: &mut App) {
-        // app.add_plugins(WorldInspectorPlugin::new());
+        if self.inspector {
+            app.add_plugins(WorldInspectorPlugin::new());
+        }
+        app.add_plugins(overlay::NetworkOverlayPlugin { enabled: self.inspector });
     }
-}
\ No newline at end of file
+}
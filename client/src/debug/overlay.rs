@@ -0,0 +1,185 @@
+//! Network debug overlay, to investigate prediction and interpolation issues:
+//! - the tails of the `Confirmed` snakes are drawn as ghosts, next to the predicted and interpolated tails
+//! - a marker is drawn at the head of our snake every time a rollback happens
+//! - the interpolation window (`InterpolateStatus` start and end ticks) of the interpolated snakes is displayed
+//! - the simulated network conditions can be switched with `LocalInput::CycleLinkPreset`
+//!
+//! The overlay is enabled at startup with `--inspector`, and can be toggled with `LocalInput::ToggleNetworkOverlay`
+use std::time::Duration;
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::prelude::client::*;
+
+use shared::network::protocol::prelude::*;
+
+use crate::inputs::LocalInput;
+use crate::network::conditioner::CurrentLink;
+use crate::network::inputs::Owned;
+use crate::NetworkStats;
+
+const CONFIRMED_COLOR: Color = Color::rgba(0.7, 0.7, 0.7, 0.6);
+const PREDICTED_COLOR: Color = Color::GREEN;
+const INTERPOLATED_COLOR: Color = Color::FUCHSIA;
+const ROLLBACK_COLOR: Color = Color::RED;
+const ROLLBACK_MARKER_DURATION: Duration = Duration::from_secs(2);
+const FONT_SIZE: f32 = 14.0;
+
+/// Whether the overlay is displayed
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub(crate) struct NetworkOverlay {
+    pub(crate) enabled: bool,
+}
+
+/// Positions of the recent rollbacks
+#[derive(Resource, Debug, Default)]
+struct RollbackMarkers {
+    markers: Vec<(Vec2, Timer)>,
+    /// number of rollbacks that were already marked
+    last_rollbacks: u32,
+}
+
+#[derive(Component)]
+struct OverlayText;
+
+pub(crate) struct NetworkOverlayPlugin {
+    pub(crate) enabled: bool,
+}
+
+impl Plugin for NetworkOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        // resources
+        app.insert_resource(NetworkOverlay { enabled: self.enabled });
+        app.init_resource::<RollbackMarkers>();
+        // systems
+        app.add_systems(Startup, spawn_overlay_text);
+        app.add_systems(Update, (toggle_overlay, record_rollbacks, cycle_link_preset.run_if(overlay_enabled)));
+        app.add_systems(PostUpdate, (draw_ghosts, draw_rollbacks, update_overlay_text)
+            .after(InterpolationSet::VisualInterpolation)
+            .run_if(overlay_enabled));
+    }
+}
+
+fn overlay_enabled(overlay: Res<NetworkOverlay>) -> bool {
+    overlay.enabled
+}
+
+fn spawn_overlay_text(mut commands: Commands, overlay: Res<NetworkOverlay>) {
+    commands.spawn((
+        TextBundle::from_section("", TextStyle { font_size: FONT_SIZE, ..default() })
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                ..default()
+            }),
+        if overlay.enabled { Visibility::Inherited } else { Visibility::Hidden },
+        OverlayText,
+    ));
+}
+
+fn toggle_overlay(
+    mut overlay: ResMut<NetworkOverlay>,
+    action: Query<&ActionState<LocalInput>, With<Owned>>,
+    mut text: Query<&mut Visibility, With<OverlayText>>,
+) {
+    let Ok(action) = action.get_single() else {
+        return;
+    };
+    if action.just_pressed(&LocalInput::ToggleNetworkOverlay) {
+        overlay.enabled = !overlay.enabled;
+        for mut visibility in text.iter_mut() {
+            *visibility = if overlay.enabled { Visibility::Inherited } else { Visibility::Hidden };
+        }
+    }
+}
+
+fn cycle_link_preset(
+    mut link: ResMut<CurrentLink>,
+    action: Query<&ActionState<LocalInput>, With<Owned>>,
+) {
+    if action.get_single().is_ok_and(|action| action.just_pressed(&LocalInput::CycleLinkPreset)) {
+        link.next_preset();
+    }
+}
+
+/// Add a marker at the head of our snake when a new rollback happens
+fn record_rollbacks(
+    time: Res<Time>,
+    stats: Res<NetworkStats>,
+    mut markers: ResMut<RollbackMarkers>,
+    predicted: Query<&TailPoints, With<Predicted>>,
+) {
+    let markers = markers.as_mut();
+    markers.markers.retain_mut(|(_, timer)| !timer.tick(time.delta()).finished());
+    if stats.rollbacks > markers.last_rollbacks {
+        markers.last_rollbacks = stats.rollbacks;
+        if let Ok(tail) = predicted.get_single() {
+            markers.markers.push((tail.front().0, Timer::new(ROLLBACK_MARKER_DURATION, TimerMode::Once)));
+        }
+    }
+}
+
+fn draw_tail(gizmos: &mut Gizmos, tail: &TailPoints, color: Color) {
+    gizmos.rect_2d(tail.front().0, 0.0, Vec2::ONE * 6.0, color);
+    for (start, end) in tail.pairs_front_to_back() {
+        gizmos.line_2d(start.0, end.0, color);
+    }
+}
+
+fn draw_ghosts(
+    mut gizmos: Gizmos,
+    confirmed: Query<&TailPoints, With<Confirmed>>,
+    predicted: Query<&TailPoints, With<Predicted>>,
+    interpolated: Query<&TailPoints, With<Interpolated>>,
+) {
+    for tail in confirmed.iter() {
+        draw_tail(&mut gizmos, tail, CONFIRMED_COLOR);
+    }
+    for tail in predicted.iter() {
+        draw_tail(&mut gizmos, tail, PREDICTED_COLOR);
+    }
+    for tail in interpolated.iter() {
+        draw_tail(&mut gizmos, tail, INTERPOLATED_COLOR);
+    }
+}
+
+fn draw_rollbacks(mut gizmos: Gizmos, markers: Res<RollbackMarkers>) {
+    for (pos, timer) in markers.markers.iter() {
+        let color = ROLLBACK_COLOR.with_a(1.0 - timer.fraction());
+        gizmos.line_2d(*pos + Vec2::new(-8.0, -8.0), *pos + Vec2::new(8.0, 8.0), color);
+        gizmos.line_2d(*pos + Vec2::new(-8.0, 8.0), *pos + Vec2::new(8.0, -8.0), color);
+        gizmos.circle_2d(*pos, 12.0, color);
+    }
+}
+
+fn update_overlay_text(
+    stats: Res<NetworkStats>,
+    link: Res<CurrentLink>,
+    interpolated: Query<(Entity, &InterpolateStatus<TailPoints>), With<Interpolated>>,
+    mut text: Query<&mut Text, With<OverlayText>>,
+) {
+    let Ok(mut text) = text.get_single_mut() else {
+        return;
+    };
+    let mut lines = vec![
+        format!(
+            "link: {} latency: {:?} jitter: {:?} loss: {:.1}%",
+            link.preset.map_or("custom".to_string(), |preset| format!("{preset:?}")),
+            link.settings.latency,
+            link.settings.jitter,
+            link.settings.loss * 100.0,
+        ),
+        format!(
+            "rollbacks: {} ({} ticks)  desyncs: {}",
+            stats.rollbacks, stats.rollback_ticks, stats.desyncs,
+        ),
+    ];
+    lines.extend(interpolated.iter().map(|(entity, status)| format!(
+        "{entity:?} current: {:?} start: {:?} end: {:?}",
+        status.current_tick,
+        status.start.as_ref().map(|(tick, _)| *tick),
+        status.end.as_ref().map(|(tick, _)| *tick),
+    )));
+    text.sections[0].value = lines.join("\n");
+}
@@ -0,0 +1,168 @@
+//! Visual effects triggered by the messages of the server:
+//! - when a snake dies, particles burst out along its tail
+//! - when a food is eaten, it flies towards the head of the snake that ate it and then gets despawned
+//!   (the server stops replicating eaten food instead of despawning it on the clients, so that we can animate it)
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use lightyear::client::events::MessageEvent;
+use lightyear::prelude::client::*;
+
+use shared::network::protocol::prelude::*;
+
+/// Distance between two particles along the tail of a dead snake
+const PARTICLE_SPACING: f32 = 10.0;
+const MAX_PARTICLES_PER_DEATH: usize = 200;
+const PARTICLE_SIZE: f32 = 5.0;
+const PARTICLE_SPEED: f32 = 80.0;
+const PARTICLE_LIFETIME: Duration = Duration::from_millis(800);
+const ABSORB_DURATION: Duration = Duration::from_millis(250);
+
+pub(crate) struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        // resources
+        app.init_resource::<LastTails>();
+        // systems
+        app.add_systems(Update, (
+            (spawn_death_particles, remember_tails).chain(),
+            update_particles,
+            (start_food_absorb, animate_food_absorb).chain(),
+        ));
+    }
+}
+
+/// Tail and color of the snake of each player during the last frame.
+/// The death message can arrive after the dead snake was despawned, so we cannot read its tail when we get it
+#[derive(Resource, Default)]
+struct LastTails(HashMap<Entity, (Vec<Vec2>, Color)>);
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    timer: Timer,
+}
+
+/// Food that was eaten by `snake`, and is flying towards its head
+#[derive(Component)]
+pub(crate) struct Absorbed {
+    snake: Entity,
+    start: Vec2,
+    timer: Timer,
+}
+
+fn remember_tails(
+    mut last_tails: ResMut<LastTails>,
+    snakes: Query<(&TailPoints, &HasPlayer), Without<Confirmed>>,
+    players: Query<Option<&SnakeAppearance>, With<Player>>,
+) {
+    last_tails.0.clear();
+    for (tail, has_player) in snakes.iter() {
+        let color = players.get(has_player.0).ok().flatten().map_or(Color::WHITE, |appearance| appearance.color());
+        last_tails.0.insert(has_player.0, (tail.0.iter().map(|(pos, _)| *pos).collect(), color));
+    }
+}
+
+/// Points every `PARTICLE_SPACING` along the polyline `points`
+fn sample_polyline(points: &[Vec2]) -> Vec<Vec2> {
+    let mut samples = vec![];
+    for pair in points.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        let count = (from.distance(to) / PARTICLE_SPACING).ceil().max(1.0) as usize;
+        samples.extend((0..count).map(|i| from.lerp(to, i as f32 / count as f32)));
+    }
+    samples.extend(points.last());
+    samples
+}
+
+fn spawn_death_particles(
+    mut commands: Commands,
+    last_tails: Res<LastTails>,
+    mut messages: EventReader<MessageEvent<SnakeCollision>>,
+) {
+    for message in messages.read() {
+        let Some((tail, color)) = last_tails.0.get(&message.message().killed) else {
+            continue;
+        };
+        let samples = sample_polyline(tail);
+        let step = (samples.len() / MAX_PARTICLES_PER_DEATH).max(1);
+        for (i, pos) in samples.into_iter().step_by(step).enumerate() {
+            // spread the directions with the golden angle, so that neighbouring particles fly apart
+            let angle = i as f32 * TAU * 0.381_966;
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: *color,
+                        custom_size: Some(Vec2::splat(PARTICLE_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(pos.extend(2.0)),
+                    ..default()
+                },
+                Particle {
+                    velocity: Vec2::from_angle(angle) * PARTICLE_SPEED * (0.5 + (i % 3) as f32 * 0.25),
+                    timer: Timer::new(PARTICLE_LIFETIME, TimerMode::Once),
+                },
+            ));
+        }
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut particle, mut transform, mut sprite) in particles.iter_mut() {
+        if particle.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation += (particle.velocity * time.delta_seconds()).extend(0.0);
+        sprite.color.set_a(1.0 - particle.timer.fraction());
+    }
+}
+
+fn start_food_absorb(
+    mut commands: Commands,
+    mut messages: EventReader<MessageEvent<FoodCollision>>,
+    food: Query<&Position, With<FoodMarker>>,
+) {
+    for message in messages.read() {
+        let message = message.message();
+        let Ok(pos) = food.get(message.food) else {
+            continue;
+        };
+        commands.entity(message.food).insert(Absorbed {
+            snake: message.snake,
+            start: pos.0,
+            timer: Timer::new(ABSORB_DURATION, TimerMode::Once),
+        });
+    }
+}
+
+fn animate_food_absorb(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut food: Query<(Entity, &mut Absorbed, &mut Transform)>,
+    confirmed: Query<&Confirmed>,
+    tails: Query<&TailPoints>,
+) {
+    for (entity, mut absorbed, mut transform) in food.iter_mut() {
+        if absorbed.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        // the message refers to the confirmed snake, but we want to fly towards the head that is displayed
+        let visual_snake = confirmed.get(absorbed.snake).ok()
+            .and_then(|confirmed| confirmed.predicted.or(confirmed.interpolated))
+            .unwrap_or(absorbed.snake);
+        let target = tails.get(visual_snake).map_or(absorbed.start, |tail| tail.front().0);
+        let progress = absorbed.timer.fraction();
+        transform.translation = absorbed.start.lerp(target, progress * progress).extend(transform.translation.z);
+        transform.scale = Vec3::splat(1.0 - progress);
+    }
+}
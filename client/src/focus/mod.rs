@@ -0,0 +1,77 @@
+//! Which snake the local player is looking at.
+//!
+//! The `FocusTarget` component on the local player is the single source of truth for the camera, the HUD and the
+//! audio: it points to our own predicted snake while we are alive, and to the interpolated snake of our killer
+//! after we die. Spectators point it to the player they are watching.
+use bevy::prelude::*;
+use lightyear::client::events::MessageEvent;
+use lightyear::prelude::client::*;
+
+use shared::network::protocol::prelude::*;
+
+use crate::network::inputs::Owned;
+
+/// Snake entity that the local player is focused on
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub(crate) struct FocusTarget(pub(crate) Entity);
+
+pub(crate) struct FocusPlugin;
+
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        // systems
+        app.add_systems(Update, (focus_killer, focus_own_snake));
+    }
+}
+
+/// Interpolated snake of `player`, if it has one
+pub(crate) fn interpolated_snake(
+    player: Entity,
+    players: &Query<&Children, With<Player>>,
+    snakes: &Query<(), (With<TailPoints>, With<Interpolated>)>,
+) -> Option<Entity> {
+    players.get(player).ok()?.iter().copied().find(|child| snakes.contains(*child))
+}
+
+/// When we die, follow the snake that killed us
+fn focus_killer(
+    mut commands: Commands,
+    mut messages: EventReader<MessageEvent<SnakeCollision>>,
+    player: Query<Entity, With<Owned>>,
+    players: Query<&Children, With<Player>>,
+    snakes: Query<(), (With<TailPoints>, With<Interpolated>)>,
+) {
+    let Ok(player_entity) = player.get_single() else {
+        return;
+    };
+    for message in messages.read() {
+        let message = message.message();
+        if message.killed != player_entity {
+            continue;
+        }
+        // we might have collided with our own tail, in which case there is nothing to follow
+        match interpolated_snake(message.killer, &players, &snakes).filter(|_| message.killer != player_entity) {
+            Some(snake) => {
+                debug!(?snake, "Focusing on the killer's snake");
+                commands.entity(player_entity).insert(FocusTarget(snake));
+            }
+            None => {
+                commands.entity(player_entity).remove::<FocusTarget>();
+            }
+        }
+    }
+}
+
+/// When we (re)spawn, focus back on our own snake
+fn focus_own_snake(
+    mut commands: Commands,
+    player: Query<Entity, With<Owned>>,
+    my_snake: Query<Entity, (Added<HasPlayer>, With<Predicted>)>,
+) {
+    let Ok(player_entity) = player.get_single() else {
+        return;
+    };
+    if let Some(snake) = my_snake.iter().next() {
+        commands.entity(player_entity).insert(FocusTarget(snake));
+    }
+}
@@ -0,0 +1,194 @@
+//! Heads-up display: stats of the snake that we are focused on (see `FocusTarget`) and of the connection.
+//!
+//! The HUD can be toggled with `LocalInput::ToggleHud`. The whole UI is scaled with the height of the window so that
+//! it stays readable on small and large windows.
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowResized};
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::prelude::client::*;
+use lightyear::prelude::TickManager;
+
+use shared::movement::{ACCELERATION_RATIO, BASE_ACCELERATION, MAX_BOOST_SPEED, MIN_SPEED};
+use shared::network::protocol::prelude::*;
+
+use crate::focus::FocusTarget;
+use crate::inputs::LocalInput;
+use crate::network::delay::AdaptiveDelay;
+use crate::network::inputs::Owned;
+use crate::NetworkStats;
+
+/// Window height for which the UI scale is 1
+const REFERENCE_HEIGHT: f32 = 720.0;
+const FONT_SIZE: f32 = 18.0;
+const GAUGE_WIDTH: f32 = 150.0;
+const GAUGE_HEIGHT: f32 = 8.0;
+
+pub(crate) struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_hud);
+        app.add_systems(Update, (toggle_hud, scale_ui, update_hud));
+    }
+}
+
+#[derive(Component)]
+struct Hud;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+enum HudText {
+    Length,
+    Rank,
+    Network,
+    Interpolation,
+    Ticks,
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+enum HudGauge {
+    Speed,
+    Friction,
+}
+
+fn spawn_hud(mut commands: Commands) {
+    let text = |label: HudText| (TextBundle::from_section("", TextStyle { font_size: FONT_SIZE, ..default() }), label);
+    let gauge = |parent: &mut ChildBuilder, name: &str, gauge: HudGauge, color: Color| {
+        parent.spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(6.0),
+                ..default()
+            },
+            ..default()
+        }).with_children(|row| {
+            row.spawn(TextBundle::from_section(name, TextStyle { font_size: FONT_SIZE, ..default() }));
+            row.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(GAUGE_WIDTH),
+                    height: Val::Px(GAUGE_HEIGHT),
+                    ..default()
+                },
+                background_color: Color::rgba(1.0, 1.0, 1.0, 0.2).into(),
+                ..default()
+            }).with_children(|background| {
+                background.spawn((NodeBundle {
+                    style: Style {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                }, gauge));
+            });
+        });
+    };
+    commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        },
+        background_color: Color::rgba(0.0, 0.0, 0.0, 0.4).into(),
+        ..default()
+    }, Hud)).with_children(|parent| {
+        parent.spawn(text(HudText::Length));
+        parent.spawn(text(HudText::Rank));
+        gauge(parent, "Speed", HudGauge::Speed, Color::YELLOW);
+        gauge(parent, "Friction", HudGauge::Friction, Color::ORANGE_RED);
+        parent.spawn(text(HudText::Network));
+        parent.spawn(text(HudText::Interpolation));
+        parent.spawn(text(HudText::Ticks));
+    });
+}
+
+fn toggle_hud(
+    action: Query<&ActionState<LocalInput>, With<Owned>>,
+    mut hud: Query<&mut Visibility, With<Hud>>,
+) {
+    let Ok(action) = action.get_single() else {
+        return;
+    };
+    if action.just_pressed(&LocalInput::ToggleHud) {
+        for mut visibility in hud.iter_mut() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+/// Scale the UI with the height of the window
+fn scale_ui(
+    mut ui_scale: ResMut<UiScale>,
+    mut resized: EventReader<WindowResized>,
+    window: Query<&Window, With<PrimaryWindow>>,
+) {
+    if resized.read().last().is_none() && !ui_scale.is_added() {
+        return;
+    }
+    if let Ok(window) = window.get_single() {
+        ui_scale.0 = (window.height() / REFERENCE_HEIGHT).clamp(0.5, 2.0);
+    }
+}
+
+fn update_hud(
+    stats: Res<NetworkStats>,
+    delay: Res<AdaptiveDelay>,
+    tick_manager: Res<TickManager>,
+    player: Query<&FocusTarget, With<Owned>>,
+    snakes: Query<(&TailLength, &Speed, &Acceleration)>,
+    other_snakes: Query<(Entity, &TailLength), Without<Confirmed>>,
+    confirmed: Query<&Confirmed>,
+    mut texts: Query<(&HudText, &mut Text)>,
+    mut gauges: Query<(&HudGauge, &mut Style)>,
+) {
+    let focus = player.get_single().ok().map(|focus| focus.0);
+    let snake = focus.and_then(|entity| snakes.get(entity).ok());
+    // the most recent server tick that we received
+    let server_tick = confirmed.iter().map(|confirmed| confirmed.tick).max();
+    for (label, mut text) in texts.iter_mut() {
+        text.sections[0].value = match label {
+            HudText::Length => snake.map_or("Length: -".to_string(), |(length, _, _)| format!("Length: {:.0}", length.current_size)),
+            HudText::Rank => match snake {
+                Some((length, _, _)) => {
+                    let others: Vec<f32> = other_snakes.iter()
+                        .filter(|(entity, _)| Some(*entity) != focus)
+                        .map(|(_, other)| other.current_size)
+                        .collect();
+                    let rank = 1 + others.iter().filter(|other| **other > length.current_size).count();
+                    format!("Rank: {rank}/{}", others.len() + 1)
+                }
+                None => "Rank: -".to_string(),
+            },
+            HudText::Network => format!(
+                "RTT: {:.0}ms  Jitter: {:.0}ms",
+                stats.rtt.as_secs_f32() * 1000.0,
+                stats.jitter.as_secs_f32() * 1000.0,
+            ),
+            HudText::Interpolation => format!(
+                "Interp delay: {:.0}ms (target {:.0}ms)",
+                delay.delay.as_secs_f32() * 1000.0,
+                delay.target.as_secs_f32() * 1000.0,
+            ),
+            HudText::Ticks => match server_tick {
+                Some(server_tick) => format!("Tick: {:?}  Server: {:?}", tick_manager.tick(), server_tick),
+                None => format!("Tick: {:?}", tick_manager.tick()),
+            },
+        };
+    }
+    for (gauge, mut style) in gauges.iter_mut() {
+        let fraction = match (gauge, snake) {
+            (HudGauge::Speed, Some((_, speed, _))) => (speed.0 - MIN_SPEED) / (MAX_BOOST_SPEED - MIN_SPEED),
+            // friction with other snakes makes the acceleration positive
+            (HudGauge::Friction, Some((_, _, acceleration))) => acceleration.0 / (BASE_ACCELERATION.abs() * ACCELERATION_RATIO),
+            (_, None) => 0.0,
+        };
+        style.width = Val::Percent(fraction.clamp(0.0, 1.0) * 100.0);
+    }
+}
@@ -7,6 +7,17 @@ use leafwing_input_manager::Actionlike;
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Reflect, Actionlike)]
 pub enum LocalInput {
     ToggleCamera,
+    ToggleHud,
+    ToggleNetworkOverlay,
+    CycleLinkPreset,
+    // spectator camera
+    NextTarget,
+    PreviousTarget,
+    FreeCamera,
+    CameraUp,
+    CameraDown,
+    CameraLeft,
+    CameraRight,
 }
 
 pub struct LocalInputsPlugin;
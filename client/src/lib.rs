@@ -1,20 +1,31 @@
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 
 use bevy::app::{App, PluginGroup};
-use bevy::DefaultPlugins;
+use bevy::{DefaultPlugins, MinimalPlugins};
+use bevy::input::InputPlugin;
 use bevy::log::{Level, LogPlugin};
 use clap::Parser;
+use lightyear::prelude::{ClientId, IoConfig};
 
-use shared::network::config::Transports;
+use shared::network::config::{LinkArgs, LinkSettings, Transports};
+use shared::network::protocol::prelude::{HeadStyle, SkinPreference, SnakePattern};
 use shared::SharedPlugin;
 
 pub(crate) mod network;
+pub use network::conditioner::CurrentLink;
+pub use network::stats::NetworkStats;
 mod render;
 mod debug;
+mod effects;
 mod collision;
 mod camera;
+mod focus;
+mod hud;
 mod inputs;
 mod menu;
+mod replay;
+mod spectator;
 
 // Use a port of 0 to automatically select a port
 pub const CLIENT_PORT: u16 = 0;
@@ -41,6 +52,40 @@ pub struct Cli {
 
     #[arg(short, long, value_enum, default_value_t = Transports::WebTransport)]
     transport: Transports,
+
+    /// Watch a replay file instead of connecting to a server
+    #[arg(long)]
+    replay: Option<PathBuf>,
+
+    #[command(flatten)]
+    link: LinkArgs,
+
+    /// Join as a spectator, without a snake
+    #[arg(long, default_value = "false")]
+    spectate: bool,
+
+    /// Preferred hue of the snake, in degrees (the server picks another one if it clashes with a nearby snake)
+    #[arg(long)]
+    hue: Option<f32>,
+
+    /// Preferred pattern of the snake
+    #[arg(long, value_enum)]
+    pattern: Option<SnakePattern>,
+
+    /// Preferred head style of the snake
+    #[arg(long, value_enum)]
+    head: Option<HeadStyle>,
+}
+
+impl Cli {
+    /// Skin to request from the server, if any option was set
+    fn skin_preference(&self) -> Option<SkinPreference> {
+        (self.hue.is_some() || self.pattern.is_some() || self.head.is_some()).then(|| SkinPreference {
+            hue: self.hue,
+            pattern: self.pattern.unwrap_or_default(),
+            head: self.head.unwrap_or_default(),
+        })
+    }
 }
 
 pub fn app(cli: Cli) -> App {
@@ -51,17 +96,58 @@ pub fn app(cli: Cli) -> App {
         update_subscriber: None,
     }));
 
-    app.add_plugins(network::NetworkPlugin {
-        client_id: cli.client_id,
-        client_port: cli.client_port,
-        server_addr: (cli.server_addr, cli.server_port).into(),
-        transport: cli.transport,
-    });
-    app.add_plugins(inputs::LocalInputsPlugin);
+    let skin = cli.skin_preference();
+    if let Some(path) = cli.replay {
+        app.add_plugins(replay::ReplayViewerPlugin { path });
+        return app;
+    }
+
+    let server_addr = (cli.server_addr, cli.server_port).into();
+    let link = network::conditioner::CurrentLink {
+        settings: cli.link.settings(network::config::DEFAULT_LINK),
+        preset: cli.link.link_preset,
+    };
+    let io = network::config::io_config(cli.client_port, server_addr, cli.transport, link.settings);
+    add_game_plugins(&mut app, cli.client_id, server_addr, io, cli.spectate, skin);
     app.add_plugins(camera::CameraPlugin);
-    app.add_plugins(collision::CollisionPlugin);
-    app.add_plugins(debug::DebugPlugin);
+    app.add_plugins(spectator::SpectatorCameraPlugin);
+    app.add_plugins(network::conditioner::LinkConditionerPlugin { link });
+    app.add_plugins(debug::DebugPlugin { inspector: cli.inspector });
     app.add_plugins(render::RenderPlugin);
-    app.add_plugins(SharedPlugin);
+    app.add_plugins(effects::EffectsPlugin);
+    app.add_plugins(hud::HudPlugin);
+    app
+}
+
+/// Client app without rendering or window that communicates with the server through `io`.
+/// Used to run clients in the same process as the server (for example in the integration tests)
+pub fn local_app(client_id: ClientId, server_addr: SocketAddr, io: IoConfig, spectate: bool) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, InputPlugin));
+    add_game_plugins(&mut app, client_id, server_addr, io, spectate, None);
+    // the io of local apps has no link conditioner, but it can be added at runtime
+    let link = network::conditioner::CurrentLink {
+        settings: LinkSettings::default(),
+        preset: None,
+    };
+    app.add_plugins(network::conditioner::LinkConditionerPlugin { link });
     app
-}
\ No newline at end of file
+}
+
+/// Plugins that handle the game logic (networking, inputs, collisions, simulation)
+fn add_game_plugins(
+    app: &mut App,
+    client_id: ClientId,
+    server_addr: SocketAddr,
+    io: IoConfig,
+    spectate: bool,
+    skin: Option<SkinPreference>,
+) {
+    app.add_plugins(network::NetworkPluginGroup::new(client_id, server_addr, io).build());
+    app.add_plugins(inputs::LocalInputsPlugin);
+    app.add_plugins(spectator::SpectatorPlugin { spectate });
+    app.add_plugins(network::skin::SkinPlugin { preference: skin });
+    app.add_plugins(collision::CollisionPlugin);
+    app.add_plugins(focus::FocusPlugin);
+    app.add_plugins(SharedPlugin);
+}
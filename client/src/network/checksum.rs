@@ -0,0 +1,74 @@
+//! Detect desyncs: compare the checksums sent by the server with the checksums of our confirmed snakes
+//! at the same tick
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use lightyear::client::events::MessageEvent;
+use lightyear::prelude::client::*;
+use lightyear::prelude::Tick;
+
+use shared::movement::checksum::snake_checksum;
+use shared::network::protocol::prelude::*;
+
+use crate::network::stats::NetworkStats;
+
+/// Number of confirmed states for which we keep the checksum
+const CHECKSUM_HISTORY_SIZE: usize = 128;
+
+pub(crate) struct ChecksumPlugin;
+
+/// Checksums of the last confirmed states of a snake
+#[derive(Component, Debug, Default)]
+pub(crate) struct ChecksumHistory(VecDeque<(Tick, u64)>);
+
+impl Plugin for ChecksumPlugin {
+    fn build(&self, app: &mut App) {
+        // systems
+        app.add_systems(Update, (record_confirmed_checksums, check_checksums).chain());
+    }
+}
+
+fn record_confirmed_checksums(
+    mut commands: Commands,
+    mut snakes: Query<
+        (Entity, &Confirmed, &TailPoints, &TailLength, &Speed, &Acceleration, Option<&mut ChecksumHistory>),
+        Or<(Changed<TailPoints>, Changed<TailLength>, Changed<Speed>, Changed<Acceleration>)>,
+    >,
+) {
+    for (entity, confirmed, tail, length, speed, acceleration, history) in snakes.iter_mut() {
+        let entry = (confirmed.tick, snake_checksum(tail, length, speed, acceleration));
+        match history {
+            Some(mut history) => {
+                if history.0.len() == CHECKSUM_HISTORY_SIZE {
+                    history.0.pop_front();
+                }
+                history.0.push_back(entry);
+            }
+            None => {
+                commands.entity(entity).insert(ChecksumHistory(VecDeque::from([entry])));
+            }
+        }
+    }
+}
+
+fn check_checksums(
+    mut stats: ResMut<NetworkStats>,
+    mut messages: EventReader<MessageEvent<StateChecksum>>,
+    histories: Query<&ChecksumHistory>,
+) {
+    for message in messages.read() {
+        let tick = message.message().tick;
+        for (entity, server_checksum) in message.message().snakes.iter() {
+            // we only know the confirmed state at the ticks where we received a replication update for this snake
+            let Some((_, checksum)) = histories.get(*entity).ok()
+                .and_then(|history| history.0.iter().find(|(t, _)| *t == tick)) else {
+                trace!(?entity, ?tick, "No confirmed state to compare with the server checksum");
+                continue;
+            };
+            if checksum != server_checksum {
+                stats.desyncs += 1;
+                warn!(?entity, ?tick, ?checksum, ?server_checksum, "Desync detected: the confirmed state does not match the server");
+            }
+        }
+    }
+}
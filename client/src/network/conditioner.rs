@@ -0,0 +1,63 @@
+//! Change the simulated network conditions while the game is running (from the debug overlay).
+//!
+//! The link conditioner is part of the io of the connection, and cannot be changed on a live connection. So we update
+//! the io in the `ClientConfig` and disconnect on purpose: the connection is rebuilt from the config when we reconnect,
+//! and the session is resumed (see `SessionPlugin`).
+//!
+//! Every change of the link therefore goes through a short disconnection. The server treats it like any other lost
+//! connection: with `GraceMode::Straight` our snake keeps going straight (and can die) until the session is resumed.
+use bevy::prelude::*;
+use lightyear::prelude::client::*;
+
+use shared::network::config::{LinkPreset, LinkSettings};
+
+/// Network conditions currently simulated by the link conditioner
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct CurrentLink {
+    pub settings: LinkSettings,
+    /// preset that the settings come from, if any
+    pub preset: Option<LinkPreset>,
+}
+
+impl CurrentLink {
+    /// Switch to the preset that comes after the current one
+    pub(crate) fn next_preset(&mut self) {
+        let next = match self.preset {
+            Some(preset) => {
+                let index = LinkPreset::ALL.iter().position(|p| *p == preset).unwrap_or(0);
+                LinkPreset::ALL[(index + 1) % LinkPreset::ALL.len()]
+            }
+            None => LinkPreset::ALL[0],
+        };
+        self.preset = Some(next);
+        self.settings = next.settings();
+    }
+}
+
+pub(crate) struct LinkConditionerPlugin {
+    pub(crate) link: CurrentLink,
+}
+
+impl Plugin for LinkConditionerPlugin {
+    fn build(&self, app: &mut App) {
+        // resources
+        app.insert_resource(self.link);
+        // systems
+        app.add_systems(Update, apply_link_change);
+    }
+}
+
+fn apply_link_change(
+    link: Res<CurrentLink>,
+    mut config: ResMut<ClientConfig>,
+    mut net: ResMut<ClientConnection>,
+) {
+    if !link.is_changed() || link.is_added() {
+        return;
+    }
+    info!(settings = ?link.settings, preset = ?link.preset, "Changing the link conditioner");
+    if let NetConfig::Netcode { io, .. } = &mut config.net {
+        io.conditioner = Some(link.settings.conditioner());
+    }
+    let _ = net.disconnect().map_err(|e| error!(?e, "Failed to disconnect"));
+}
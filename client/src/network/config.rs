@@ -5,21 +5,23 @@ use bevy::prelude::default;
 use lightyear::prelude::*;
 use lightyear::prelude::client::*;
 
-use shared::network::config::{KEY, PROTOCOL_ID, shared_config, Transports};
+use shared::network::config::{KEY, LinkSettings, PROTOCOL_ID, shared_config, Transports};
 use shared::network::protocol::{GameProtocol, protocol};
 
-pub(crate) fn build_plugin(
-    client_id: ClientId,
+/// Network conditions used by the client when no link conditioner option is set
+pub(crate) const DEFAULT_LINK: LinkSettings = LinkSettings {
+    latency: Duration::from_millis(40),
+    jitter: Duration::from_millis(4),
+    loss: 0.01,
+};
+
+/// Create the io (transport + link conditioner) used by the client
+pub(crate) fn io_config(
     client_port: u16,
     server_addr: SocketAddr,
     transport: Transports,
-) -> ClientPlugin<GameProtocol> {
-    let auth = Authentication::Manual {
-        server_addr,
-        client_id,
-        private_key: KEY,
-        protocol_id: PROTOCOL_ID,
-    };
+    link: LinkSettings,
+) -> IoConfig {
     let client_addr = SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), client_port);
     let certificate_digest =
         String::from("6c594425dd0c8664c188a0ad6e641b39ff5f007e5bcfc1e72c7a7f2f38ecf819")
@@ -35,19 +37,29 @@ pub(crate) fn build_plugin(
         },
         Transports::WebSocket => TransportConfig::WebSocketClient { server_addr },
     };
-    let link_conditioner = LinkConditionerConfig {
-        incoming_latency: Duration::from_millis(40),
-        incoming_jitter: Duration::from_millis(4),
-        incoming_loss: 0.01,
+    IoConfig::from_transport(transport_config).with_conditioner(link.conditioner())
+}
+
+pub(crate) fn build_plugin(
+    client_id: ClientId,
+    server_addr: SocketAddr,
+    io: IoConfig,
+) -> ClientPlugin<GameProtocol> {
+    let auth = Authentication::Manual {
+        server_addr,
+        client_id,
+        private_key: KEY,
+        protocol_id: PROTOCOL_ID,
     };
     let config = ClientConfig {
         shared: shared_config(),
         net: NetConfig::Netcode {
             auth,
             config: NetcodeConfig::default(),
-            io: IoConfig::from_transport(transport_config).with_conditioner(link_conditioner),
+            io,
         },
         interpolation: InterpolationConfig {
+            // initial delay, it is then adapted to the jitter of the connection (see `AdaptiveDelayPlugin`)
             delay: InterpolationDelay::default().with_send_interval_ratio(2.0),
             // do not do linear interpolation per component, instead we provide our own interpolation logic
             custom_interpolation_logic: true,
@@ -0,0 +1,182 @@
+//! Adapt the interpolation delay to the quality of the connection.
+//!
+//! We measure how irregularly the server snapshots arrive (compared to the ticks that they contain), and keep the
+//! interpolation delay just big enough that the interpolated snakes always have an end state to interpolate towards.
+//! The delay changes slowly, so that the interpolation clock is warped smoothly instead of jumping.
+use std::time::Duration;
+
+use bevy::prelude::*;
+use lightyear::prelude::client::*;
+use lightyear::prelude::{MainSet, Tick};
+
+use shared::network::config::{FIXED_TIMESTEP_HZ, SERVER_SEND_HZ};
+use shared::network::protocol::prelude::*;
+
+const MIN_DELAY: Duration = Duration::from_millis((1000.0 / SERVER_SEND_HZ) as u64);
+const MAX_DELAY: Duration = Duration::from_millis(250);
+/// Number of jitter deviations that we add on top of the send interval
+const JITTER_MARGIN: f32 = 4.0;
+/// Jitter that is added to the estimate when an interpolated snake runs out of end states
+const STARVATION_PENALTY: Duration = Duration::from_millis(5);
+/// Smoothing factor of the jitter estimate
+const JITTER_SMOOTHING: f32 = 0.1;
+/// Maximum change of the delay per second of real time
+const MAX_WARP: f32 = 0.02;
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub(crate) struct AdaptiveDelay {
+    /// interpolation delay that is currently used
+    pub(crate) delay: Duration,
+    /// delay that we are moving towards
+    pub(crate) target: Duration,
+    /// estimated jitter of the snapshot arrival times
+    pub(crate) jitter: Duration,
+    /// most recent server tick that we received, and when we received it
+    last_arrival: Option<(Tick, Duration)>,
+    /// whether an interpolated snake is currently out of end states
+    starving: bool,
+}
+
+impl Default for AdaptiveDelay {
+    fn default() -> Self {
+        let delay = MIN_DELAY * 2;
+        Self {
+            delay,
+            target: delay,
+            jitter: Duration::ZERO,
+            last_arrival: None,
+            starving: false,
+        }
+    }
+}
+
+impl AdaptiveDelay {
+    /// Penalize the jitter once per starvation episode (a snake can stay starved for many frames)
+    pub(crate) fn update_starvation(&mut self, starved: bool) {
+        if starved && !self.starving {
+            trace!(jitter = ?self.jitter, "Interpolated snake ran out of end states");
+            self.jitter = (self.jitter + STARVATION_PENALTY).min(max_jitter());
+        }
+        self.starving = starved;
+    }
+}
+
+pub(crate) struct AdaptiveDelayPlugin;
+
+impl Plugin for AdaptiveDelayPlugin {
+    fn build(&self, app: &mut App) {
+        // resources
+        app.init_resource::<AdaptiveDelay>();
+        // systems
+        app.add_systems(PreUpdate, (measure_jitter, detect_starvation, apply_delay)
+            .chain()
+            .after(MainSet::Receive));
+    }
+}
+
+/// Jitter above which the target delay is already `MAX_DELAY`
+fn max_jitter() -> Duration {
+    (MAX_DELAY - MIN_DELAY).div_f32(JITTER_MARGIN)
+}
+
+/// Delay that gives the interpolated snakes enough margin for the measured jitter
+pub(crate) fn target_delay(jitter: Duration) -> Duration {
+    (MIN_DELAY + jitter.mul_f32(JITTER_MARGIN)).clamp(MIN_DELAY, MAX_DELAY)
+}
+
+/// Move the delay towards the target, at most by `MAX_WARP` seconds per second
+pub(crate) fn warp_delay(delay: Duration, target: Duration, dt: Duration) -> Duration {
+    let max_step = dt.mul_f32(MAX_WARP);
+    if target > delay {
+        (delay + max_step).min(target)
+    } else {
+        delay.saturating_sub(max_step).max(target)
+    }
+}
+
+fn measure_jitter(
+    time: Res<Time<Real>>,
+    mut adaptive: ResMut<AdaptiveDelay>,
+    confirmed: Query<&Confirmed>,
+) {
+    let Some(server_tick) = confirmed.iter().map(|confirmed| confirmed.tick).max() else {
+        return;
+    };
+    let now = time.elapsed();
+    match adaptive.last_arrival {
+        Some((last_tick, last_time)) if server_tick > last_tick => {
+            // the snapshot should arrive `ticks` fixed timesteps after the previous one
+            let ticks = (server_tick - last_tick) as f32;
+            let expected = ticks / FIXED_TIMESTEP_HZ as f32;
+            let deviation = ((now - last_time).as_secs_f32() - expected).abs();
+            let jitter = adaptive.jitter.as_secs_f32() * (1.0 - JITTER_SMOOTHING) + deviation * JITTER_SMOOTHING;
+            adaptive.jitter = Duration::from_secs_f32(jitter).min(max_jitter());
+            adaptive.last_arrival = Some((server_tick, now));
+        }
+        Some(_) => {}
+        None => adaptive.last_arrival = Some((server_tick, now)),
+    }
+}
+
+/// If an interpolated snake has no end state to interpolate towards, the delay is too short
+fn detect_starvation(
+    mut adaptive: ResMut<AdaptiveDelay>,
+    interpolated: Query<&InterpolateStatus<TailPoints>, With<Interpolated>>,
+) {
+    let starved = interpolated.iter().any(|status| status.start.is_some() && status.end.is_none());
+    adaptive.update_starvation(starved);
+}
+
+fn apply_delay(
+    time: Res<Time<Real>>,
+    mut adaptive: ResMut<AdaptiveDelay>,
+    mut config: ResMut<ClientConfig>,
+) {
+    adaptive.target = target_delay(adaptive.jitter);
+    adaptive.delay = warp_delay(adaptive.delay, adaptive.target, time.delta());
+    // lightyear uses the biggest of the two values, so we only set the minimum delay
+    config.interpolation.delay = InterpolationDelay::default()
+        .with_send_interval_ratio(0.0)
+        .with_min_delay(adaptive.delay);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_delay_bounds() {
+        assert_eq!(target_delay(Duration::ZERO), MIN_DELAY);
+        assert_eq!(target_delay(Duration::from_millis(5)), MIN_DELAY + Duration::from_millis(20));
+        assert_eq!(target_delay(Duration::from_secs(1)), MAX_DELAY);
+    }
+
+    #[test]
+    fn test_warp_delay_is_smooth() {
+        let dt = Duration::from_millis(100);
+        let delay = Duration::from_millis(50);
+        // we move by 2ms per 100ms towards the target
+        assert_eq!(warp_delay(delay, Duration::from_millis(100), dt), Duration::from_millis(52));
+        assert_eq!(warp_delay(delay, Duration::from_millis(10), dt), Duration::from_millis(48));
+        // we don't overshoot
+        assert_eq!(warp_delay(delay, Duration::from_millis(51), dt), Duration::from_millis(51));
+    }
+
+    #[test]
+    fn test_starvation_penalty_once_per_episode() {
+        let mut adaptive = AdaptiveDelay::default();
+        adaptive.update_starvation(true);
+        adaptive.update_starvation(true);
+        assert_eq!(adaptive.jitter, STARVATION_PENALTY);
+        // a new episode is penalized again
+        adaptive.update_starvation(false);
+        adaptive.update_starvation(true);
+        assert_eq!(adaptive.jitter, STARVATION_PENALTY * 2);
+        // the jitter stays bounded
+        for _ in 0..1000 {
+            adaptive.update_starvation(false);
+            adaptive.update_starvation(true);
+        }
+        assert_eq!(adaptive.jitter, max_jitter());
+    }
+}
@@ -31,6 +31,9 @@ impl Plugin for NetworkInputsPlugin {
 }
 
 
+/// Key used to respawn after dying
+pub(crate) const SPAWN_KEY: KeyCode = KeyCode::Enter;
+
 // TODO: move somewhere else?
 /// Component that indicates that the entity is owned by the local client
 #[derive(Component)]
@@ -46,10 +49,21 @@ fn add_game_inputs(
             commands.entity(entity).insert(
                 (
                     InputMap::new([
-                        (DeadGameAction::Spawn, KeyCode::Enter),
+                        (DeadGameAction::Spawn, SPAWN_KEY),
                     ]),
                     InputMap::new([
                         (LocalInput::ToggleCamera, KeyCode::KeyT),
+                        (LocalInput::ToggleHud, KeyCode::KeyH),
+                        (LocalInput::ToggleNetworkOverlay, KeyCode::F3),
+                        (LocalInput::CycleLinkPreset, KeyCode::F4),
+                        (LocalInput::NextTarget, KeyCode::Tab),
+                        (LocalInput::NextTarget, KeyCode::KeyE),
+                        (LocalInput::PreviousTarget, KeyCode::KeyQ),
+                        (LocalInput::FreeCamera, KeyCode::KeyF),
+                        (LocalInput::CameraUp, KeyCode::KeyW),
+                        (LocalInput::CameraDown, KeyCode::KeyS),
+                        (LocalInput::CameraLeft, KeyCode::KeyA),
+                        (LocalInput::CameraRight, KeyCode::KeyD),
                     ]),
                     ActionState::<DeadGameAction>::default(),
                     ActionState::<LocalInput>::default(),
@@ -76,6 +90,10 @@ fn add_movement_inputs(
                 (PlayerMovement::Up, KeyCode::KeyW),
                 (PlayerMovement::Down, KeyCode::ArrowDown),
                 (PlayerMovement::Down, KeyCode::KeyS),
+                (PlayerMovement::Boost, KeyCode::Space),
+                (PlayerMovement::Boost, KeyCode::ShiftLeft),
+                (PlayerMovement::Brake, KeyCode::ControlLeft),
+                (PlayerMovement::Brake, KeyCode::KeyB),
             ]), ActionState::<PlayerMovement>::default())
         );
     }
@@ -33,7 +33,8 @@ pub(crate) fn interpolate_snake(
             continue;
         };
         let Some((_, length_start)) = &length_status.start else {
-            panic!("we should also have a tail length start");
+            // the length history can lag behind the tail history for a frame
+            continue;
         };
         let end = tail_status.end.as_ref().map(|x| x.0);
         info!(
@@ -51,17 +52,21 @@ pub(crate) fn interpolate_snake(
             continue;
         };
         let Some((_, length_end)) = &length_status.end else {
-            panic!("we should also have a tail length end");
+            continue;
         };
         info!(start = ?tail_start.front(), end = ?tail_end.front(), "Updating tail");
-        assert_ne!(start_tick, end_tick);
+        if start_tick == end_tick {
+            continue;
+        }
 
         // we need to interpolate between the two tails. It will be similar to the start tail with some added points
         // at the front, and then we will remove points from the back to respect the length
         *tail = tail_start.clone();
 
         // interpolation ratio
-        let t = tail_status.interpolation_fraction().unwrap();
+        let Some(t) = tail_status.interpolation_fraction() else {
+            continue;
+        };
 
         // linear interpolation for the length
         *length = length_start.clone() * (1.0 - t) + length_end.clone() * t;
@@ -97,8 +102,12 @@ pub(crate) fn interpolate_snake(
             continue;
         }
         if segment_idx == usize::MAX {
-            // the difference between start/end is bigger than the length of the snake
-            panic!("could not find segment on which the head point is");
+            // the difference between start/end is bigger than the length of the snake (for example after
+            // a long packet loss): we cannot follow the tail path, so we snap to the end state
+            trace!("could not find segment on which the head point is, snapping to the end tail");
+            *tail = tail_end.clone();
+            *length = length_end.clone();
+            continue;
         }
 
         // 2. now move the head point by `pos_distance_to_move` while remaining on the end tail path
@@ -1,36 +1,61 @@
 use std::net::SocketAddr;
 
+use bevy::app::PluginGroupBuilder;
 use bevy::prelude::*;
 use lightyear::prelude::client::*;
-use lightyear::prelude::ClientId;
+use lightyear::prelude::{ClientId, IoConfig};
 
-use shared::network::config::Transports;
+use shared::network::protocol::GameProtocol;
 
+use crate::network::checksum::ChecksumPlugin;
+use crate::network::delay::AdaptiveDelayPlugin;
 use crate::network::inputs::NetworkInputsPlugin;
 use crate::network::interpolation::InterpolationPlugin;
+use crate::network::session::SessionPlugin;
+use crate::network::stats::NetworkStatsPlugin;
 
+mod checksum;
+pub(crate) mod conditioner;
 pub(crate) mod config;
+pub(crate) mod delay;
 pub(crate) mod inputs;
 mod interpolation;
 mod connect;
+mod session;
+pub(crate) mod skin;
+pub(crate) mod stats;
 
-pub(crate) struct NetworkPlugin {
-    pub(crate) client_id: ClientId,
-    pub(crate) client_port: u16,
-    pub(crate) server_addr: SocketAddr,
-    pub(crate) transport: Transports,
+pub(crate) struct NetworkPluginGroup {
+    pub(crate) lightyear: ClientPlugin<GameProtocol>,
 }
 
+impl PluginGroup for NetworkPluginGroup {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(self.lightyear)
+            .add(NetworkPlugin)
+    }
+}
+
+impl NetworkPluginGroup {
+    pub fn new(client_id: ClientId, server_addr: SocketAddr, io: IoConfig) -> Self {
+        let lightyear = config::build_plugin(client_id, server_addr, io);
+        Self {
+            lightyear,
+        }
+    }
+}
+
+pub(crate) struct NetworkPlugin;
+
 impl Plugin for NetworkPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(config::build_plugin(
-            self.client_id,
-            self.client_port,
-            self.server_addr,
-            self.transport,
-        ));
         app.add_plugins(NetworkInputsPlugin);
         app.add_plugins(InterpolationPlugin);
+        app.add_plugins(AdaptiveDelayPlugin);
+        app.add_plugins(SessionPlugin);
+        app.add_plugins(NetworkStatsPlugin);
+        app.add_plugins(ChecksumPlugin);
         app.add_systems(Startup, connect);
     }
 }
@@ -0,0 +1,85 @@
+//! Join the match when we connect, and keep track of the session token sent by the server, so that we can take back
+//! our player and snake if we get disconnected briefly
+use bevy::prelude::*;
+use lightyear::client::events::{ConnectEvent, DisconnectEvent, MessageEvent};
+use lightyear::prelude::client::*;
+
+use shared::network::protocol::prelude::*;
+
+use crate::spectator::Spectating;
+
+pub(crate) struct SessionPlugin;
+
+/// Token of the current session, if the server sent one
+#[derive(Resource, Debug, Default)]
+pub(crate) struct Session {
+    pub(crate) token: Option<u64>,
+    /// the server disconnected us on purpose, we should not reconnect
+    pub(crate) kicked: bool,
+}
+
+impl Plugin for SessionPlugin {
+    fn build(&self, app: &mut App) {
+        // resources
+        app.init_resource::<Session>();
+        // systems
+        app.add_systems(Update, (receive_session_token, receive_kick, resume_session, reconnect).chain());
+    }
+}
+
+fn receive_session_token(
+    mut session: ResMut<Session>,
+    mut messages: EventReader<MessageEvent<SessionToken>>,
+) {
+    // the server sends a token when we connect, and sends the resumed token again if it accepted to resume our
+    // session, so the last token received is always the one of our current player
+    for message in messages.read() {
+        session.token = Some(message.message().token);
+    }
+}
+
+fn receive_kick(
+    mut session: ResMut<Session>,
+    mut messages: EventReader<MessageEvent<Kicked>>,
+) {
+    for message in messages.read() {
+        warn!(reason = ?message.message().reason, "Kicked by the server");
+        session.token = None;
+        session.kicked = true;
+    }
+}
+
+/// When we connect and we already have a session token, ask the server to resume the session.
+/// Then join the match (the server ignores it if it resumed our session)
+fn resume_session(
+    session: Res<Session>,
+    spectating: Res<Spectating>,
+    mut connections: EventReader<ConnectEvent>,
+    mut connection_manager: ResMut<ClientConnectionManager>,
+) {
+    for _ in connections.read() {
+        if let Some(token) = session.token {
+            info!("Resuming session");
+            let _ = connection_manager.send_message::<GameChannel, _>(ResumeSession { token })
+                .map_err(|e| error!(?e, "Failed to send resume session message"));
+        }
+        let _ = connection_manager.send_message::<GameChannel, _>(Join { spectate: spectating.0 })
+            .map_err(|e| error!(?e, "Failed to send join message"));
+    }
+}
+
+/// Try to reconnect right away if we lost the connection, but not if the server kicked us
+fn reconnect(
+    session: Res<Session>,
+    mut disconnections: EventReader<DisconnectEvent>,
+    mut net: ResMut<ClientConnection>,
+) {
+    for _ in disconnections.read() {
+        if session.kicked {
+            info!("Disconnected by the server, not reconnecting");
+            continue;
+        }
+        info!("Disconnected from the server, reconnecting");
+        let _ = net.connect().map_err(|e| error!(?e, "Failed to reconnect"));
+    }
+}
@@ -0,0 +1,37 @@
+//! Send the preferred look of our snake to the server
+use bevy::prelude::*;
+use lightyear::client::events::ConnectEvent;
+use lightyear::prelude::client::*;
+
+use shared::network::protocol::prelude::*;
+
+pub(crate) struct SkinPlugin {
+    pub(crate) preference: Option<SkinPreference>,
+}
+
+#[derive(Resource, Debug)]
+struct PreferredSkin(SkinPreference);
+
+impl Plugin for SkinPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(preference) = self.preference.clone() else {
+            return;
+        };
+        // resources
+        app.insert_resource(PreferredSkin(preference));
+        // systems
+        app.add_systems(Update, send_skin_preference);
+    }
+}
+
+/// Send the preference every time we connect; the server applies it to our snake as soon as it receives it
+fn send_skin_preference(
+    preferred: Res<PreferredSkin>,
+    mut connections: EventReader<ConnectEvent>,
+    mut connection_manager: ResMut<ClientConnectionManager>,
+) {
+    for _ in connections.read() {
+        let _ = connection_manager.send_message::<GameChannel, _>(preferred.0.clone())
+            .map_err(|e| error!(?e, "Failed to send skin preference message"));
+    }
+}
@@ -0,0 +1,69 @@
+//! Collect statistics about the connection to the server: round-trip time, bandwidth, rollbacks and desyncs
+use std::time::Duration;
+
+use bevy::diagnostic::{DiagnosticsStore, DiagnosticsPlugin};
+use bevy::prelude::*;
+use lightyear::client::prediction::rollback::{Rollback, RollbackState};
+use lightyear::prelude::client::*;
+use lightyear::transport::io::IoDiagnosticsPlugin;
+
+use shared::network::protocol::prelude::*;
+
+pub(crate) struct NetworkStatsPlugin;
+
+#[derive(Resource, Debug, Default, Clone)]
+pub struct NetworkStats {
+    pub rtt: Duration,
+    pub jitter: Duration,
+    /// bytes received per second
+    pub bytes_in: f64,
+    /// bytes sent per second
+    pub bytes_out: f64,
+    /// number of rollbacks since the client started
+    pub rollbacks: u32,
+    /// number of ticks that were re-simulated during rollbacks
+    pub rollback_ticks: u32,
+    /// number of snake states that did not match the checksum sent by the server
+    pub desyncs: u32,
+    /// number of rollback ticks at the last frame, used to detect the start of a new rollback
+    last_rollback_ticks: u32,
+}
+
+impl Plugin for NetworkStatsPlugin {
+    fn build(&self, app: &mut App) {
+        // plugins
+        if !app.is_plugin_added::<DiagnosticsPlugin>() {
+            app.add_plugins(DiagnosticsPlugin);
+        }
+        // resources
+        app.init_resource::<NetworkStats>();
+        // systems
+        app.add_systems(FixedUpdate, count_rollback_ticks.run_if(in_rollback));
+        app.add_systems(PostUpdate, update_network_stats);
+    }
+}
+
+fn in_rollback(rollback: Option<Res<Rollback>>) -> bool {
+    rollback.is_some_and(|rollback| matches!(rollback.state, RollbackState::ShouldRollback { .. }))
+}
+
+fn count_rollback_ticks(mut stats: ResMut<NetworkStats>) {
+    stats.rollback_ticks += 1;
+}
+
+fn update_network_stats(
+    mut stats: ResMut<NetworkStats>,
+    connection: Res<ClientConnectionManager>,
+    diagnostics: Res<DiagnosticsStore>,
+) {
+    // all the ticks of a rollback are re-simulated during a single frame
+    if stats.rollback_ticks > stats.last_rollback_ticks {
+        stats.rollbacks += 1;
+        stats.last_rollback_ticks = stats.rollback_ticks;
+    }
+    stats.rtt = connection.ping_manager.rtt();
+    stats.jitter = connection.ping_manager.jitter();
+    let smoothed = |id| diagnostics.get(id).and_then(|d| d.smoothed()).unwrap_or_default();
+    stats.bytes_in = smoothed(IoDiagnosticsPlugin::BYTES_IN);
+    stats.bytes_out = smoothed(IoDiagnosticsPlugin::BYTES_OUT);
+}
@@ -1,16 +1,49 @@
+use std::f32::consts::TAU;
+
 use bevy::prelude::*;
 use shared::network::protocol::prelude::*;
 
+use crate::effects::Absorbed;
+
+/// Size of the food sprites, in world units
+const FOOD_SIZE: f32 = 10.0;
+const FOOD_COLOR: Color = Color::GREEN;
+/// Number of pulses per second
+const PULSE_FREQUENCY: f32 = 1.5;
+/// Relative change of size during a pulse
+const PULSE_AMPLITUDE: f32 = 0.2;
+
 pub(crate) struct FoodRenderPlugin;
 
 
 impl FoodRenderPlugin {
-    fn draw_food(
-        mut gizmos: Gizmos,
-        query: Query<&Position, With<FoodMarker>>,
+    /// Add a sprite to the food when it is spawned (the food never moves)
+    fn add_food_sprite(
+        mut commands: Commands,
+        query: Query<(Entity, &Position), Added<FoodMarker>>,
+    ) {
+        for (entity, pos) in query.iter() {
+            commands.entity(entity).insert(SpriteBundle {
+                sprite: Sprite {
+                    color: FOOD_COLOR,
+                    custom_size: Some(Vec2::splat(FOOD_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(pos.0.extend(0.0)),
+                ..default()
+            });
+        }
+    }
+
+    fn pulse_food(
+        time: Res<Time>,
+        mut query: Query<(&Position, &mut Transform), (With<FoodMarker>, Without<Absorbed>)>,
     ) {
-        for pos in query.iter() {
-            gizmos.circle_2d(pos.0, 5.0, Color::GREEN);
+        for (pos, mut transform) in query.iter_mut() {
+            // offset the phase with the position so that the food doesn't all pulse at the same time
+            let phase = (pos.0.x + pos.0.y) * 0.01;
+            let scale = 1.0 + PULSE_AMPLITUDE * (time.elapsed_seconds() * PULSE_FREQUENCY * TAU + phase).sin();
+            transform.scale = Vec3::splat(scale);
         }
     }
 }
@@ -18,6 +51,6 @@ impl FoodRenderPlugin {
 
 impl Plugin for FoodRenderPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, FoodRenderPlugin::draw_food);
+        app.add_systems(Update, (FoodRenderPlugin::add_food_sprite, FoodRenderPlugin::pulse_food));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,98 @@
+//! List of the last kills, in the top-right corner of the screen.
+//! The names are drawn with the color of the players' snakes
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use lightyear::client::events::MessageEvent;
+
+use shared::network::protocol::prelude::*;
+
+/// How long a kill stays in the feed
+const KILL_FEED_DURATION: Duration = Duration::from_secs(5);
+const MAX_KILL_FEED_ENTRIES: usize = 5;
+const FONT_SIZE: f32 = 18.0;
+
+pub(crate) struct KillFeedPlugin;
+
+impl Plugin for KillFeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_kill_feed);
+        app.add_systems(Update, (add_kills, expire_kills));
+    }
+}
+
+#[derive(Component)]
+struct KillFeed;
+
+#[derive(Component)]
+struct KillFeedEntry(Timer);
+
+fn spawn_kill_feed(mut commands: Commands) {
+    commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            top: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::FlexEnd,
+            ..default()
+        },
+        ..default()
+    }, KillFeed));
+}
+
+fn add_kills(
+    mut commands: Commands,
+    mut messages: EventReader<MessageEvent<SnakeCollision>>,
+    feed: Query<(Entity, Option<&Children>), With<KillFeed>>,
+    players: Query<(&Player, Option<&SnakeAppearance>)>,
+) {
+    let Ok((feed, entries)) = feed.get_single() else {
+        return;
+    };
+    // the oldest entries are at the top
+    let mut entries: VecDeque<Entity> = entries.into_iter().flatten().copied().collect();
+    let section = |player: Entity| {
+        let (name, color) = players.get(player)
+            .map_or(("?".to_string(), Color::WHITE), |(player, appearance)| {
+                (player.name.clone(), appearance.map_or(Color::WHITE, |appearance| appearance.color()))
+            });
+        TextSection::new(name, TextStyle { font_size: FONT_SIZE, color, ..default() })
+    };
+    for message in messages.read() {
+        let message = message.message();
+        let sections = if message.killer == message.killed {
+            vec![section(message.killed), TextSection::new(" crashed", TextStyle { font_size: FONT_SIZE, ..default() })]
+        } else {
+            vec![
+                section(message.killer),
+                TextSection::new(" killed ", TextStyle { font_size: FONT_SIZE, ..default() }),
+                section(message.killed),
+            ]
+        };
+        if entries.len() >= MAX_KILL_FEED_ENTRIES {
+            if let Some(oldest) = entries.pop_front() {
+                commands.entity(oldest).despawn_recursive();
+            }
+        }
+        let entry = commands.spawn((
+            TextBundle::from_sections(sections),
+            KillFeedEntry(Timer::new(KILL_FEED_DURATION, TimerMode::Once)),
+        )).id();
+        commands.entity(feed).add_child(entry);
+        entries.push_back(entry);
+    }
+}
+
+fn expire_kills(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut entries: Query<(Entity, &mut KillFeedEntry)>,
+) {
+    for (entity, mut entry) in entries.iter_mut() {
+        if entry.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Minimap in the corner of the screen.
+//!
+//! It shows the bounds of the map, the snakes as dots (ours is highlighted) and the density of food as a coarse heatmap.
+//! It only uses the entities that exist on the client, so it respects interest management: snakes and food that
+//! the server doesn't replicate to us are not shown.
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+use lightyear::prelude::client::*;
+
+use shared::map::{MAP_SIZE, MapMarker, MapSize};
+use shared::network::protocol::prelude::*;
+
+/// Size of the minimap on the screen, in pixels
+const MINIMAP_SIZE: f32 = 180.0;
+/// Number of cells of the food heatmap along each axis
+const HEATMAP_CELLS: usize = 16;
+/// Number of food items in a cell for which the heatmap is fully opaque
+const HEATMAP_SATURATION: f32 = 10.0;
+const DOT_SIZE: f32 = 4.0;
+const UPDATE_INTERVAL: Duration = Duration::from_millis(250);
+
+pub(crate) struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_minimap);
+        app.add_systems(Update, (update_heatmap, update_dots).run_if(on_timer(UPDATE_INTERVAL)));
+    }
+}
+
+/// Cell of the food heatmap, with its (column, row) from the top-left corner
+#[derive(Component)]
+struct HeatmapCell(usize, usize);
+
+/// Parent of the dots that represent the snakes
+#[derive(Component)]
+struct MinimapDots;
+
+fn spawn_minimap(mut commands: Commands) {
+    let cell_size = MINIMAP_SIZE / HEATMAP_CELLS as f32;
+    commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            bottom: Val::Px(10.0),
+            width: Val::Px(MINIMAP_SIZE),
+            height: Val::Px(MINIMAP_SIZE),
+            border: UiRect::all(Val::Px(1.0)),
+            display: Display::Grid,
+            grid_template_columns: RepeatedGridTrack::px(HEATMAP_CELLS as u16, cell_size),
+            grid_template_rows: RepeatedGridTrack::px(HEATMAP_CELLS as u16, cell_size),
+            ..default()
+        },
+        background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+        border_color: Color::WHITE.into(),
+        ..default()
+    }).with_children(|parent| {
+        for row in 0..HEATMAP_CELLS {
+            for column in 0..HEATMAP_CELLS {
+                parent.spawn((NodeBundle::default(), HeatmapCell(column, row)));
+            }
+        }
+        parent.spawn((NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(0.0),
+                top: Val::Px(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            ..default()
+        }, MinimapDots));
+    });
+}
+
+fn map_size(map: &Query<&MapSize, With<MapMarker>>) -> Vec2 {
+    map.get_single().map_or(Vec2::splat(MAP_SIZE), |size| Vec2::new(size.width, size.height))
+}
+
+/// Position of a world point on the minimap, as a fraction of the minimap size from the top-left corner
+fn minimap_position(pos: Vec2, map_size: Vec2) -> Vec2 {
+    let relative = (pos / map_size + 0.5).clamp(Vec2::ZERO, Vec2::ONE);
+    Vec2::new(relative.x, 1.0 - relative.y)
+}
+
+fn update_heatmap(
+    map: Query<&MapSize, With<MapMarker>>,
+    food: Query<&Position, With<FoodMarker>>,
+    mut cells: Query<(&HeatmapCell, &mut BackgroundColor)>,
+) {
+    let map_size = map_size(&map);
+    let mut counts = [[0usize; HEATMAP_CELLS]; HEATMAP_CELLS];
+    for pos in food.iter() {
+        let cell = (minimap_position(pos.0, map_size) * HEATMAP_CELLS as f32).as_uvec2();
+        let column = (cell.x as usize).min(HEATMAP_CELLS - 1);
+        let row = (cell.y as usize).min(HEATMAP_CELLS - 1);
+        counts[row][column] += 1;
+    }
+    for (cell, mut color) in cells.iter_mut() {
+        let density = (counts[cell.1][cell.0] as f32 / HEATMAP_SATURATION).min(1.0);
+        *color = Color::rgba(0.0, 1.0, 0.0, density * 0.6).into();
+    }
+}
+
+fn update_dots(
+    mut commands: Commands,
+    map: Query<&MapSize, With<MapMarker>>,
+    dots: Query<Entity, With<MinimapDots>>,
+    snakes: Query<(&TailPoints, Has<Predicted>), Without<Confirmed>>,
+) {
+    let Ok(dots) = dots.get_single() else {
+        return;
+    };
+    let map_size = map_size(&map);
+    commands.entity(dots).despawn_descendants().with_children(|parent| {
+        for (tail, is_ours) in snakes.iter() {
+            let pos = minimap_position(tail.front().0, map_size) * 100.0;
+            // our own snake is the only predicted one
+            let (size, color) = if is_ours { (DOT_SIZE * 1.5, Color::YELLOW) } else { (DOT_SIZE, Color::RED) };
+            parent.spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(pos.x),
+                    top: Val::Percent(pos.y),
+                    width: Val::Px(size),
+                    height: Val::Px(size),
+                    margin: UiRect::all(Val::Px(-size / 2.0)),
+                    ..default()
+                },
+                background_color: color.into(),
+                ..default()
+            });
+        }
+    });
+}
@@ -1,8 +1,10 @@
 use bevy::app::{App, Plugin};
 
 pub(crate) mod snake;
-mod camera;
-mod food;
+pub(crate) mod camera;
+pub(crate) mod food;
+mod minimap;
+mod kill_feed;
 
 
 pub(crate) struct RenderPlugin;
@@ -12,5 +14,7 @@ impl Plugin for RenderPlugin {
         app.add_plugins(snake::SnakeRenderPlugin);
         app.add_plugins(camera::CameraPlugin);
         app.add_plugins(food::FoodRenderPlugin);
+        app.add_plugins(minimap::MinimapPlugin);
+        app.add_plugins(kill_feed::KillFeedPlugin);
     }
 }
\ No newline at end of file
@@ -1,11 +1,31 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
 use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
 use bevy::transform::TransformSystem;
+use bevy::utils::HashMap;
 use lightyear::prelude::client::*;
-use lightyear::prelude::{TickManager};
 use shared::network::protocol::GameProtocol;
 
 use shared::network::protocol::prelude::*;
 
+/// Width of the tail, in world units
+const TAIL_WIDTH: f32 = 8.0;
+/// Number of vertices on the circle of a rounded joint
+const JOINT_RESOLUTION: usize = 12;
+const HEAD_SIZE: f32 = 16.0;
+/// Length of the stripes and distance between the dots of the patterns. The patterns are anchored in the world
+const PATTERN_LENGTH: f32 = 16.0;
+/// Snakes are drawn above the food
+const SNAKE_Z: f32 = 1.0;
+const DEFAULT_APPEARANCE: SnakeAppearance = SnakeAppearance {
+    hue: 240.0,
+    pattern: SnakePattern::Solid,
+    head: HeadStyle::Arrow,
+};
+
 pub(crate) struct SnakeRenderPlugin;
 
 impl Plugin for SnakeRenderPlugin {
@@ -13,11 +33,7 @@ impl Plugin for SnakeRenderPlugin {
         // Plugins
         // Visually interpolate the tails since they are updated during FixedUpdate
         app.add_plugins(VisualInterpolationPlugin::<TailPoints, GameProtocol>::default());
-        // Draw the snakes after visual interpolation is computed
-        app.add_systems(PostUpdate, draw_snakes
-            .before(TransformSystem::TransformPropagate)
-            .after(InterpolationSet::VisualInterpolation)
-        );
+        app.add_plugins(SnakeMeshPlugin);
         // Add visual interpolation after the component gets added on the predicted entity
         app.add_systems(PreUpdate, add_visual_interpolation_to_predicted_snake.after(
             PredictionSet::SpawnHistoryFlush
@@ -25,6 +41,45 @@ impl Plugin for SnakeRenderPlugin {
     }
 }
 
+/// Build and update the meshes of the snakes (predicted, interpolated, or simulated locally when watching a replay)
+pub(crate) struct SnakeMeshPlugin;
+
+impl Plugin for SnakeMeshPlugin {
+    fn build(&self, app: &mut App) {
+        // Update the meshes after visual interpolation is computed
+        app.add_systems(PostUpdate, (spawn_snake_meshes, update_snake_meshes, despawn_snake_meshes)
+            .chain()
+            .before(TransformSystem::TransformPropagate)
+            .after(InterpolationSet::VisualInterpolation)
+        );
+    }
+}
+
+/// Entity that draws the tail of `snake`.
+///
+/// It is separate from the snake because the snake is a child of its player, which doesn't have a transform
+#[derive(Component, Debug)]
+struct SnakeMesh {
+    snake: Entity,
+    head: Entity,
+    mesh: Handle<Mesh>,
+    head_mesh: Handle<Mesh>,
+    head_material: Handle<ColorMaterial>,
+    /// appearance used to build the current meshes
+    appearance: Option<SnakeAppearance>,
+    /// geometry of the segments and joints of the last mesh, so that only the pieces that changed are recomputed
+    cache: GeometryCache,
+}
+
+/// Appearance of the snakes of the replays, which don't record the appearance chosen by the server
+pub(crate) fn replay_appearance(client_id: u64) -> SnakeAppearance {
+    // golden ratio: consecutive ids get very different hues
+    SnakeAppearance {
+        hue: (client_id as f64 * 0.618_033_988_75).fract() as f32 * 360.0,
+        ..DEFAULT_APPEARANCE
+    }
+}
+
 /// Adds visual interpolation to the predicted tails
 fn add_visual_interpolation_to_predicted_snake(
     mut commands: Commands,
@@ -35,35 +90,230 @@ fn add_visual_interpolation_to_predicted_snake(
     }
 }
 
-/// System that draws the boxed of the player positions.
-/// The components should be replicated from the server to the client
-pub(crate) fn draw_snakes(
-    mut gizmos: Gizmos,
-    tails: Query<&TailPoints, Without<Confirmed>>,
-    interp_snake: Query<&TailPoints, With<Interpolated>>,
-    predicted_snake: Query<&TailPoints, With<Predicted>>,
-    tick: Res<TickManager>,
+fn head_mesh(style: HeadStyle) -> Mesh {
+    let half = HEAD_SIZE / 2.0;
+    match style {
+        HeadStyle::Arrow => Triangle2d::new(Vec2::new(0.0, half), Vec2::new(-half, -half), Vec2::new(half, -half)).into(),
+        HeadStyle::Round => Circle::new(half).into(),
+        HeadStyle::Square => Rectangle::new(HEAD_SIZE, HEAD_SIZE).into(),
+    }
+}
+
+fn spawn_snake_meshes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    snakes: Query<Entity, (Added<TailPoints>, Without<Confirmed>)>,
 ) {
-    let tick = tick.tick();
-    for points in interp_snake.iter() {
-        // info!(?tick, front = ?points.front(), "interp snake");
+    for snake in snakes.iter() {
+        let mesh = meshes.add(Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default()));
+        let head_mesh = meshes.add(head_mesh(DEFAULT_APPEARANCE.head));
+        let head_material = materials.add(ColorMaterial::from(DEFAULT_APPEARANCE.color()));
+        let head = commands.spawn(MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(head_mesh.clone()),
+            material: head_material.clone(),
+            ..default()
+        }).id();
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(mesh.clone()),
+                // the colors of the tail are stored in the vertices
+                material: materials.add(ColorMaterial::from(Color::WHITE)),
+                transform: Transform::from_xyz(0.0, 0.0, SNAKE_Z),
+                ..default()
+            },
+            SnakeMesh {
+                snake,
+                head,
+                mesh,
+                head_mesh,
+                head_material,
+                appearance: None,
+                cache: GeometryCache::default(),
+            },
+        )).add_child(head);
     }
-    for points in predicted_snake.iter() {
-        // info!(?tick, front = ?points.front(), "predicted snake");
+}
+
+/// Rebuild the mesh of the snakes whose tail or appearance changed, and move the head
+fn update_snake_meshes(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut snake_meshes: Query<&mut SnakeMesh>,
+    // the snakes of the replays have their own appearance, the other snakes use the one of their player
+    snakes: Query<(Ref<TailPoints>, Option<&SnakeAppearance>, Option<&HasPlayer>)>,
+    players: Query<&SnakeAppearance, With<Player>>,
+    mut heads: Query<&mut Transform>,
+) {
+    for mut snake_mesh in snake_meshes.iter_mut() {
+        let Ok((tail, appearance, has_player)) = snakes.get(snake_mesh.snake) else {
+            continue;
+        };
+        let appearance = appearance
+            .or_else(|| has_player.and_then(|has_player| players.get(has_player.0).ok()))
+            .copied()
+            .unwrap_or(DEFAULT_APPEARANCE);
+        let appearance_changed = snake_mesh.appearance != Some(appearance);
+        if appearance_changed {
+            snake_mesh.appearance = Some(appearance);
+            snake_mesh.cache = GeometryCache::default();
+            meshes.insert(snake_mesh.head_mesh.clone(), head_mesh(appearance.head));
+            if let Some(material) = materials.get_mut(&snake_mesh.head_material) {
+                material.color = appearance.color();
+            }
+        }
+        if !tail.is_changed() && !appearance_changed {
+            continue;
+        }
+        let (head_pos, direction) = *tail.front();
+        if let Ok(mut transform) = heads.get_mut(snake_mesh.head) {
+            let delta = direction.delta();
+            // the head mesh points up
+            *transform = Transform::from_xyz(head_pos.x, head_pos.y, 0.1)
+                .with_rotation(Quat::from_rotation_z(delta.y.atan2(delta.x) - FRAC_PI_2));
+        }
+        let (positions, colors, indices) = snake_mesh.cache.build(&tail, &appearance);
+        if let Some(mesh) = meshes.get_mut(&snake_mesh.mesh) {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+            mesh.insert_indices(Indices::U32(indices));
+        }
     }
-    for points in tails.iter() {
-        // draw the head
-        gizmos.rect_2d(
-            points.front().0,
-            0.0,
-            Vec2::ONE * 10.0,
-            Color::BLUE
-        );
-        points.pairs_front_to_back().for_each(|(start, end)| {
-            gizmos.line_2d(start.0, end.0, Color::BLUE);
-            if start.0.x != end.0.x && start.0.y != end.0.y {
-                info!("DIAGONAL");
+}
+
+/// Despawn the meshes of the snakes that were despawned
+fn despawn_snake_meshes(
+    mut commands: Commands,
+    snake_meshes: Query<(Entity, &SnakeMesh)>,
+    snakes: Query<(), With<TailPoints>>,
+) {
+    for (entity, snake_mesh) in snake_meshes.iter() {
+        if !snakes.contains(snake_mesh.snake) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Triangles of one piece of the tail (a segment or a joint)
+#[derive(Debug, Clone, Default)]
+struct Piece {
+    positions: Vec<[f32; 3]>,
+    colors: Vec<[f32; 4]>,
+    /// indices relative to the first vertex of the piece
+    indices: Vec<u32>,
+}
+
+impl Piece {
+    /// Add a quad with the corners `corners` (in counter-clockwise order)
+    fn add_quad(&mut self, corners: [Vec2; 4], color: Color) {
+        let offset = self.positions.len() as u32;
+        self.positions.extend(corners.map(|p| [p.x, p.y, 0.0]));
+        self.colors.extend([color.as_linear_rgba_f32(); 4]);
+        self.indices.extend([0, 1, 2, 0, 2, 3].map(|i| i + offset));
+    }
+}
+
+type PieceKey = (u64, u64);
+
+/// Pieces of the last mesh, keyed by the points they were built from.
+///
+/// Only the points at both ends of the tail move from one frame to the next, so most pieces can be reused
+#[derive(Debug, Default)]
+struct GeometryCache {
+    pieces: HashMap<PieceKey, Piece>,
+}
+
+fn point_key(point: Vec2) -> u64 {
+    ((point.x.to_bits() as u64) << 32) | point.y.to_bits() as u64
+}
+
+impl GeometryCache {
+    /// Compute the vertices, colors and indices of the whole tail, reusing the pieces that didn't change
+    fn build(&mut self, tail: &TailPoints, appearance: &SnakeAppearance) -> (Vec<[f32; 3]>, Vec<[f32; 4]>, Vec<u32>) {
+        let mut pieces = HashMap::default();
+        let mut positions = vec![];
+        let mut colors = vec![];
+        let mut indices = vec![];
+        let mut add = |key: PieceKey, piece: Piece| {
+            let offset = positions.len() as u32;
+            positions.extend_from_slice(&piece.positions);
+            colors.extend_from_slice(&piece.colors);
+            indices.extend(piece.indices.iter().map(|i| i + offset));
+            pieces.insert(key, piece);
+        };
+        for (point, _) in tail.0.iter() {
+            let key = (point_key(*point), point_key(*point));
+            let piece = self.pieces.remove(&key).unwrap_or_else(|| joint(*point, appearance));
+            add(key, piece);
+        }
+        for ((from, _), (to, _)) in tail.pairs_front_to_back() {
+            let key = (point_key(*from), point_key(*to));
+            let piece = self.pieces.remove(&key).unwrap_or_else(|| segment(*from, *to, appearance));
+            add(key, piece);
+        }
+        self.pieces = pieces;
+        (positions, colors, indices)
+    }
+}
+
+/// Rectangle of width `TAIL_WIDTH` between two points, with the pattern of the snake
+fn segment(from: Vec2, to: Vec2, appearance: &SnakeAppearance) -> Piece {
+    let direction = (to - from).normalize_or_zero();
+    let normal = direction.perp() * TAIL_WIDTH / 2.0;
+    let color = appearance.color();
+    let mut piece = Piece::default();
+    if appearance.pattern == SnakePattern::Solid {
+        piece.add_quad([from + normal, from - normal, to - normal, to + normal], color);
+        return piece;
+    }
+    // split the segment at multiples of PATTERN_LENGTH, measured along its direction
+    let start = from.dot(direction);
+    let end = start + from.distance(to);
+    let at = |c: f32| from + direction * (c - start);
+    let first = (start / PATTERN_LENGTH).floor() as i32;
+    let last = (end / PATTERN_LENGTH).ceil() as i32;
+    for k in first..last {
+        let a = (k as f32 * PATTERN_LENGTH).max(start);
+        let b = ((k + 1) as f32 * PATTERN_LENGTH).min(end);
+        if b <= a {
+            continue;
+        }
+        match appearance.pattern {
+            SnakePattern::Striped => {
+                let stripe = if k.rem_euclid(2) == 0 { color } else { Color::hsl(appearance.hue, 0.7, 0.35) };
+                piece.add_quad([at(a) + normal, at(a) - normal, at(b) - normal, at(b) + normal], stripe);
+            }
+            SnakePattern::Dotted => {
+                piece.add_quad([at(a) + normal, at(a) - normal, at(b) - normal, at(b) + normal], color);
+                let center = (k as f32 + 0.5) * PATTERN_LENGTH;
+                if (a..b).contains(&center) {
+                    let dot = at(center);
+                    let half = Vec2::splat(TAIL_WIDTH / 4.0);
+                    piece.add_quad(
+                        [dot - half, dot + Vec2::new(half.x, -half.y), dot + half, dot + Vec2::new(-half.x, half.y)],
+                        Color::hsl(appearance.hue, 0.7, 0.8),
+                    );
+                }
             }
-        });
+            SnakePattern::Solid => unreachable!(),
+        }
+    }
+    piece
+}
+
+/// Disk at a corner of the tail, so that the joints are rounded
+fn joint(center: Vec2, appearance: &SnakeAppearance) -> Piece {
+    let mut positions = vec![[center.x, center.y, 0.0]];
+    positions.extend((0..JOINT_RESOLUTION).map(|i| {
+        let p = center + Vec2::from_angle(i as f32 * TAU / JOINT_RESOLUTION as f32) * TAIL_WIDTH / 2.0;
+        [p.x, p.y, 0.0]
+    }));
+    let indices = (0..JOINT_RESOLUTION as u32)
+        .flat_map(|i| [0, i + 1, (i + 1) % JOINT_RESOLUTION as u32 + 1])
+        .collect();
+    Piece {
+        colors: vec![appearance.color().as_linear_rgba_f32(); positions.len()],
+        positions,
+        indices,
     }
-}
\ No newline at end of file
+}
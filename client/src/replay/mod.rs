@@ -0,0 +1,464 @@
+//! Play back a replay recorded by the server, without connecting to a server.
+//!
+//! The snakes are simulated locally with the `MovementPlugin` and the `CollisionPlugin` (for the friction), using the
+//! inputs stored in the replay. Food growth is not simulated, the new lengths are stored in the replay.
+//! Play/pause and speed are handled by the virtual time (which drives `FixedUpdate`).
+//! The snapshots stored in the replay are used as keyframes: when we reach one we overwrite the simulated state
+//! with it, and to seek we restore the last keyframe before the target tick and then simulate the remaining ticks
+//! with `step`, which runs the same schedules as a frame of normal playback.
+//!
+//! Controls:
+//! - Space: play/pause
+//! - Left/Right: seek 5 seconds backward/forward
+//! - Up/Down: double/halve the playback speed
+//! - Home: restart from the beginning
+//! - WASD: move the camera, Q/E: zoom in/out
+use std::path::PathBuf;
+
+use bevy::ecs::system::CommandQueue;
+use bevy::prelude::*;
+use leafwing_input_manager::plugin::InputManagerSystem;
+use leafwing_input_manager::prelude::*;
+use lightyear::prelude::ClientId;
+
+use shared::movement::{MovementPlugin, SimulationSet};
+use shared::network::bundle::food::FoodBundle;
+use shared::network::bundle::snake::SnakeBundle;
+use shared::network::protocol::PlayerMovement;
+use shared::network::protocol::prelude::*;
+use shared::replay::{Replay, ReplayEvent, SnakeState, Snapshot};
+
+use crate::render;
+use crate::render::snake::replay_appearance;
+
+/// Number of seconds to skip when seeking
+const SEEK_SECONDS: f32 = 5.0;
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 16.0;
+/// Camera movement per second, in world units (multiplied by the zoom)
+const CAMERA_SPEED: f32 = 800.0;
+/// Zoom change per second
+const ZOOM_SPEED: f32 = 1.5;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Reflect, Actionlike)]
+pub enum ReplayAction {
+    TogglePause,
+    SeekBackward,
+    SeekForward,
+    SpeedUp,
+    SlowDown,
+    Restart,
+    CameraUp,
+    CameraDown,
+    CameraLeft,
+    CameraRight,
+    ZoomIn,
+    ZoomOut,
+}
+
+/// Marker for the entities spawned from the replay (they get despawned when we seek)
+#[derive(Component, Debug)]
+pub struct ReplayEntity;
+
+/// Client that controls the snake in the replay
+#[derive(Component, Debug)]
+pub struct ReplayClient(pub ClientId);
+
+#[derive(Resource, Debug)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    /// indices of the frames that contain a snapshot
+    keyframes: Vec<usize>,
+    /// next tick to simulate
+    tick: u32,
+    /// index of the first frame that has not been applied yet
+    next_frame: usize,
+    speed: f32,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        let keyframes = replay.frames.iter().enumerate()
+            .filter(|(_, frame)| frame.events.iter().any(|event| matches!(event, ReplayEvent::Snapshot(_))))
+            .map(|(i, _)| i)
+            .collect();
+        Self {
+            replay,
+            keyframes,
+            tick: 0,
+            next_frame: 0,
+            speed: 1.0,
+        }
+    }
+
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    pub fn end_tick(&self) -> u32 {
+        self.replay.frames.last().map_or(0, |frame| frame.tick + 1)
+    }
+
+    /// Events of the frame for the current tick, if any
+    fn current_events(&self) -> &[ReplayEvent] {
+        self.replay.frames.get(self.next_frame)
+            .filter(|frame| frame.tick == self.tick)
+            .map_or(&[], |frame| frame.events.as_slice())
+    }
+}
+
+/// Simulation of the replay, without rendering or controls
+pub struct ReplayPlaybackPlugin {
+    pub replay: Replay,
+}
+
+impl Plugin for ReplayPlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        // plugins
+        app.add_plugins((MovementPlugin, shared::collision::CollisionPlugin));
+        // resources
+        app.insert_resource(Time::<Fixed>::from_hz(self.replay.header.tick_rate));
+        app.insert_resource(ReplayPlayback::new(self.replay.clone()));
+        // systems
+        app.add_systems(FixedUpdate, (
+            apply_events.before(SimulationSet::Movement),
+            apply_snapshot.after(SimulationSet::Movement),
+        ));
+        app.add_systems(Update, pause_at_end);
+    }
+}
+
+/// Replay viewer: playback, rendering and controls
+pub struct ReplayViewerPlugin {
+    pub path: PathBuf,
+}
+
+impl Plugin for ReplayViewerPlugin {
+    fn build(&self, app: &mut App) {
+        let replay = Replay::read(&self.path)
+            .unwrap_or_else(|e| panic!("could not read replay {:?}: {e:?}", self.path));
+        info!(path = ?self.path, frames = replay.frames.len(), "Playing replay");
+        // plugins
+        app.add_plugins(ReplayPlaybackPlugin { replay });
+        app.add_plugins((render::camera::CameraPlugin, render::food::FoodRenderPlugin, render::snake::SnakeMeshPlugin));
+        app.add_plugins(InputManagerPlugin::<ReplayAction>::default());
+        // resources
+        app.init_resource::<ActionState<ReplayAction>>();
+        app.insert_resource(InputMap::new([
+            (ReplayAction::TogglePause, KeyCode::Space),
+            (ReplayAction::SeekBackward, KeyCode::ArrowLeft),
+            (ReplayAction::SeekForward, KeyCode::ArrowRight),
+            (ReplayAction::SpeedUp, KeyCode::ArrowUp),
+            (ReplayAction::SlowDown, KeyCode::ArrowDown),
+            (ReplayAction::Restart, KeyCode::Home),
+            (ReplayAction::CameraUp, KeyCode::KeyW),
+            (ReplayAction::CameraDown, KeyCode::KeyS),
+            (ReplayAction::CameraLeft, KeyCode::KeyA),
+            (ReplayAction::CameraRight, KeyCode::KeyD),
+            (ReplayAction::ZoomIn, KeyCode::KeyQ),
+            (ReplayAction::ZoomOut, KeyCode::KeyE),
+        ]));
+        // systems
+        app.add_systems(Startup, spawn_status_text);
+        // seeking runs the `Update` schedule, so the controls cannot run in `Update`
+        app.add_systems(PreUpdate, playback_controls.after(InputManagerSystem::Update));
+        app.add_systems(Update, (move_camera, update_status_text));
+        // registry
+        app.register_type::<ReplayAction>();
+    }
+}
+
+fn spawn_snake(commands: &mut Commands, client_id: ClientId, snake: SnakeBundle) {
+    commands.spawn((snake, ReplayEntity, ReplayClient(client_id), replay_appearance(client_id)));
+}
+
+/// Apply the events of the current tick that happen before the simulation
+fn apply_events(
+    mut commands: Commands,
+    playback: Res<ReplayPlayback>,
+    mut snakes: Query<(Entity, &ReplayClient, &mut ActionState<PlayerMovement>, &mut TailLength)>,
+) {
+    for event in playback.current_events() {
+        match event {
+            ReplayEvent::Spawn(state) => {
+                spawn_snake(&mut commands, state.client_id, snake_bundle(state));
+            }
+            ReplayEvent::Movement { client_id, input } => {
+                if let Some((_, _, mut action, _)) = snakes.iter_mut().find(|(_, client, _, _)| client.0 == *client_id) {
+                    input.apply(&mut action);
+                }
+            }
+            ReplayEvent::Death { killed, .. } => {
+                if let Some((entity, _, _, _)) = snakes.iter().find(|(_, client, _, _)| client.0 == *killed) {
+                    commands.entity(entity).despawn();
+                }
+            }
+            ReplayEvent::Grow { client_id, target_size } => {
+                if let Some((_, _, _, mut length)) = snakes.iter_mut().find(|(_, client, _, _)| client.0 == *client_id) {
+                    length.target_size = *target_size;
+                }
+            }
+            ReplayEvent::Connect(_) | ReplayEvent::Disconnect(_) | ReplayEvent::Snapshot(_) => {}
+        }
+    }
+}
+
+/// Snapshots describe the state after the simulation of their tick, so we apply them after the simulation.
+/// Then we move on to the next tick
+fn apply_snapshot(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    entities: Query<Entity, With<ReplayEntity>>,
+) {
+    let snapshot = playback.current_events().iter().find_map(|event| match event {
+        ReplayEvent::Snapshot(snapshot) => Some(snapshot.clone()),
+        _ => None,
+    });
+    if let Some(snapshot) = snapshot {
+        for entity in entities.iter() {
+            commands.entity(entity).despawn();
+        }
+        spawn_snapshot(&mut commands, &snapshot);
+    }
+    if playback.replay.frames.get(playback.next_frame).is_some_and(|frame| frame.tick == playback.tick) {
+        playback.next_frame += 1;
+    }
+    playback.tick += 1;
+}
+
+fn snake_bundle(state: &SnakeState) -> SnakeBundle {
+    let mut snake = SnakeBundle::from_tail(TailPoints(state.tail.iter().copied().collect()), state.length.clone());
+    snake.speed = Speed(state.speed);
+    snake.acceleration = Acceleration(state.acceleration);
+    state.input.apply(&mut snake.action);
+    snake
+}
+
+/// Spawn the snakes and the food of the snapshot
+fn spawn_snapshot(commands: &mut Commands, snapshot: &Snapshot) {
+    for state in snapshot.snakes.iter() {
+        spawn_snake(commands, state.client_id, snake_bundle(state));
+    }
+    for food in snapshot.food.iter() {
+        commands.spawn((FoodBundle::new(Position(*food)), ReplayEntity));
+    }
+}
+
+/// Restore the last keyframe before `target`, and simulate the remaining ticks
+pub fn seek(world: &mut World, target: u32) {
+    let target = target.min(world.resource::<ReplayPlayback>().end_tick());
+    let mut entities = world.query_filtered::<Entity, With<ReplayEntity>>();
+    let to_despawn: Vec<Entity> = entities.iter(world).collect();
+    for entity in to_despawn {
+        world.despawn(entity);
+    }
+    let mut playback = world.resource_mut::<ReplayPlayback>();
+    // the keyframe of tick k contains the state after tick k was simulated
+    let keyframe = playback.keyframes.iter().rev()
+        .copied()
+        .find(|i| playback.replay.frames[*i].tick < target);
+    let snapshot = match keyframe {
+        Some(i) => {
+            playback.tick = playback.replay.frames[i].tick + 1;
+            playback.next_frame = i + 1;
+            playback.replay.frames[i].events.iter().find_map(|event| match event {
+                ReplayEvent::Snapshot(snapshot) => Some(snapshot.clone()),
+                _ => None,
+            })
+        }
+        None => {
+            playback.tick = 0;
+            playback.next_frame = 0;
+            None
+        }
+    };
+    let ticks = target - playback.tick;
+    if let Some(snapshot) = snapshot {
+        let mut queue = CommandQueue::default();
+        spawn_snapshot(&mut Commands::new(&mut queue, world), &snapshot);
+        queue.apply(world);
+    }
+    for _ in 0..ticks {
+        step(world);
+    }
+}
+
+/// Simulate one tick like a frame of normal playback does: the snakes move in `FixedUpdate`, and the friction between
+/// them is computed in `Update`
+pub fn step(world: &mut World) {
+    world.run_schedule(FixedUpdate);
+    world.run_schedule(Update);
+}
+
+fn pause_at_end(playback: Res<ReplayPlayback>, mut time: ResMut<Time<Virtual>>) {
+    if playback.tick >= playback.end_tick() && !time.is_paused() {
+        info!("End of the replay");
+        time.pause();
+    }
+}
+
+fn playback_controls(world: &mut World) {
+    let action = world.resource::<ActionState<ReplayAction>>().clone();
+    let tick_rate = world.resource::<ReplayPlayback>().replay.header.tick_rate as f32;
+    let tick = world.resource::<ReplayPlayback>().tick;
+    if action.just_pressed(&ReplayAction::TogglePause) {
+        let mut time = world.resource_mut::<Time<Virtual>>();
+        if time.is_paused() {
+            time.unpause();
+        } else {
+            time.pause();
+        }
+    }
+    let seek_ticks = (SEEK_SECONDS * tick_rate) as u32;
+    if action.just_pressed(&ReplayAction::SeekBackward) {
+        seek(world, tick.saturating_sub(seek_ticks));
+    }
+    if action.just_pressed(&ReplayAction::SeekForward) {
+        seek(world, tick + seek_ticks);
+    }
+    if action.just_pressed(&ReplayAction::Restart) {
+        seek(world, 0);
+        world.resource_mut::<Time<Virtual>>().unpause();
+    }
+    let mut speed = world.resource::<ReplayPlayback>().speed;
+    if action.just_pressed(&ReplayAction::SpeedUp) {
+        speed = (speed * 2.0).min(MAX_SPEED);
+    }
+    if action.just_pressed(&ReplayAction::SlowDown) {
+        speed = (speed / 2.0).max(MIN_SPEED);
+    }
+    world.resource_mut::<ReplayPlayback>().speed = speed;
+    world.resource_mut::<Time<Virtual>>().set_relative_speed(speed);
+}
+
+/// Free camera
+fn move_camera(
+    time: Res<Time<Real>>,
+    action: Res<ActionState<ReplayAction>>,
+    mut camera: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+) {
+    let Ok((mut transform, mut projection)) = camera.get_single_mut() else {
+        return;
+    };
+    let delta = time.delta_seconds();
+    let directions = [
+        (ReplayAction::CameraUp, Vec2::Y),
+        (ReplayAction::CameraDown, Vec2::NEG_Y),
+        (ReplayAction::CameraLeft, Vec2::NEG_X),
+        (ReplayAction::CameraRight, Vec2::X),
+    ];
+    let movement: Vec2 = directions.iter()
+        .filter(|(action_type, _)| action.pressed(action_type))
+        .map(|(_, direction)| *direction)
+        .sum();
+    transform.translation += (movement * CAMERA_SPEED * projection.scale * delta).extend(0.0);
+    if action.pressed(&ReplayAction::ZoomIn) {
+        projection.scale /= ZOOM_SPEED.powf(delta);
+    }
+    if action.pressed(&ReplayAction::ZoomOut) {
+        projection.scale *= ZOOM_SPEED.powf(delta);
+    }
+}
+
+#[derive(Component)]
+struct StatusText;
+
+fn spawn_status_text(mut commands: Commands) {
+    commands.spawn((TextBundle::from_section("", TextStyle::default()), StatusText));
+}
+
+fn update_status_text(
+    playback: Res<ReplayPlayback>,
+    time: Res<Time<Virtual>>,
+    mut text: Query<&mut Text, With<StatusText>>,
+) {
+    let Ok(mut text) = text.get_single_mut() else {
+        return;
+    };
+    let tick_rate = playback.replay.header.tick_rate as f32;
+    text.sections[0].value = format!(
+        "{:.1}s / {:.1}s  x{}{}",
+        playback.tick as f32 / tick_rate,
+        playback.end_tick() as f32 / tick_rate,
+        playback.speed,
+        if time.is_paused() { "  (paused)" } else { "" },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashMap as Map;
+    use shared::replay::{MovementInput, ReplayFrame, ReplayHeader};
+    use shared::network::protocol::prelude::Direction;
+
+    use super::*;
+
+    fn replay() -> Replay {
+        let mut frames = vec![
+            ReplayFrame { tick: 0, events: vec![ReplayEvent::Connect(1), ReplayEvent::Spawn(SnakeState {
+                client_id: 1,
+                tail: vec![(Vec2::new(0.0, 0.0), Direction::Up), (Vec2::new(0.0, -200.0), Direction::Up)],
+                length: TailLength { current_size: 200.0, target_size: 200.0 },
+                speed: 1.0,
+                acceleration: 0.0,
+                input: MovementInput(0),
+            })] },
+            ReplayFrame { tick: 20, events: vec![ReplayEvent::Movement { client_id: 1, input: MovementInput(0b1000) }] },
+            ReplayFrame { tick: 21, events: vec![ReplayEvent::Movement { client_id: 1, input: MovementInput(0) }] },
+        ];
+        // keyframe: the snake was teleported, to check that the keyframe is used when seeking
+        frames.push(ReplayFrame {
+            tick: 50,
+            events: vec![ReplayEvent::Snapshot(Snapshot {
+                snakes: vec![SnakeState {
+                    client_id: 1,
+                    tail: vec![(Vec2::new(100.0, 100.0), Direction::Up), (Vec2::new(100.0, 0.0), Direction::Up)],
+                    length: TailLength { current_size: 100.0, target_size: 100.0 },
+                    speed: 1.0,
+                    acceleration: 0.0,
+                    input: MovementInput(0),
+                }],
+                ..default()
+            })],
+        });
+        frames.push(ReplayFrame { tick: 99, events: vec![] });
+        Replay {
+            header: ReplayHeader { seed: 0, tick_rate: 64.0, start_time: 0 },
+            frames,
+        }
+    }
+
+    fn heads(app: &mut App) -> Map<ClientId, Vec2> {
+        app.world.query::<(&ReplayClient, &TailPoints)>()
+            .iter(&app.world)
+            .map(|(client, tail)| (client.0, tail.front().0))
+            .collect()
+    }
+
+    #[test]
+    fn test_seek() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_plugins(ReplayPlaybackPlugin { replay: replay() });
+
+        // play the first 40 ticks
+        for _ in 0..40 {
+            step(&mut app.world);
+        }
+        let played = heads(&mut app);
+        assert_eq!(app.world.resource::<ReplayPlayback>().tick(), 40);
+
+        // seeking forward then back gives the same state as playing
+        seek(&mut app.world, 80);
+        let after_keyframe = heads(&mut app);
+        assert_eq!(after_keyframe[&1].x, 100.0);
+        seek(&mut app.world, 40);
+        assert_eq!(heads(&mut app), played);
+
+        // seeking from a keyframe gives the same state as playing through it
+        for _ in 40..80 {
+            step(&mut app.world);
+        }
+        assert_eq!(heads(&mut app), after_keyframe);
+    }
+}
@@ -0,0 +1,159 @@
+//! Watch the match without a snake.
+//!
+//! A client joins as a spectator when it is started with `--spectate` (see the `Join` message sent when it
+//! connects), or when the server is full. The player of a spectator has the `Spectator` component.
+//! Spectators can cycle the camera through the live players, sorted by length, or move a free camera.
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use leafwing_input_manager::prelude::{ActionState, InputMap};
+use lightyear::prelude::client::*;
+
+use shared::network::protocol::DeadGameAction;
+use shared::network::protocol::prelude::*;
+
+use crate::camera::CameraState;
+use crate::focus::{FocusTarget, interpolated_snake};
+use crate::inputs::LocalInput;
+use crate::network::inputs::Owned;
+
+/// Free camera movement per second, in world units
+const FREE_CAMERA_SPEED: f32 = 800.0;
+
+/// True if the client asked to join as a spectator
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct Spectating(pub(crate) bool);
+
+/// Which player the spectator camera follows
+#[derive(Resource, Debug, Default)]
+pub(crate) struct SpectatorCamera {
+    /// the followed player entity
+    pub(crate) target: Option<Entity>,
+    /// if true, the camera is moved with the keyboard instead of following a player
+    pub(crate) free: bool,
+}
+
+/// Join the match as a spectator if `spectate` is set
+pub(crate) struct SpectatorPlugin {
+    pub(crate) spectate: bool,
+}
+
+impl Plugin for SpectatorPlugin {
+    fn build(&self, app: &mut App) {
+        // resources
+        app.insert_resource(Spectating(self.spectate));
+        // systems
+        app.add_systems(Update, remove_spawn_inputs);
+    }
+}
+
+/// Camera controls of the spectators
+pub(crate) struct SpectatorCameraPlugin;
+
+impl Plugin for SpectatorCameraPlugin {
+    fn build(&self, app: &mut App) {
+        // resources
+        app.init_resource::<SpectatorCamera>();
+        // systems
+        app.add_systems(Update, cycle_target.run_if(is_spectator));
+        app.add_systems(PostUpdate, move_free_camera
+            .before(TransformSystem::TransformPropagate)
+            .after(InterpolationSet::VisualInterpolation)
+            .run_if(is_spectator.and_then(in_state(CameraState::Follow))));
+    }
+}
+
+pub(crate) fn is_spectator(player: Query<(), (With<Owned>, With<Spectator>)>) -> bool {
+    !player.is_empty()
+}
+
+/// Spectators cannot spawn a snake
+fn remove_spawn_inputs(
+    mut commands: Commands,
+    player: Query<Entity, (With<Owned>, Added<Spectator>)>,
+) {
+    for entity in player.iter() {
+        commands.entity(entity).remove::<(InputMap<DeadGameAction>, ActionState<DeadGameAction>)>();
+    }
+}
+
+/// Players that have a snake, from the longest snake to the shortest
+pub(crate) fn leaderboard(
+    players: &Query<(Entity, &Player, Option<&Children>), Without<Spectator>>,
+    snakes: &Query<&TailLength, With<Interpolated>>,
+) -> Vec<Entity> {
+    let mut ranked: Vec<(Entity, &Player, f32)> = players.iter()
+        .filter_map(|(entity, player, children)| {
+            let length = children.into_iter().flatten().find_map(|child| snakes.get(*child).ok())?;
+            Some((entity, player, length.current_size))
+        })
+        .collect();
+    ranked.sort_by(|(_, a, a_length), (_, b, b_length)| b_length.total_cmp(a_length).then(a.id.cmp(&b.id)));
+    ranked.into_iter().map(|(entity, _, _)| entity).collect()
+}
+
+fn cycle_target(
+    mut commands: Commands,
+    mut camera: ResMut<SpectatorCamera>,
+    action: Query<(Entity, &ActionState<LocalInput>), With<Owned>>,
+    ranked_players: Query<(Entity, &Player, Option<&Children>), Without<Spectator>>,
+    lengths: Query<&TailLength, With<Interpolated>>,
+    players: Query<&Children, With<Player>>,
+    snakes: Query<(), (With<TailPoints>, With<Interpolated>)>,
+) {
+    let Ok((player_entity, action)) = action.get_single() else {
+        return;
+    };
+    let ranking = leaderboard(&ranked_players, &lengths);
+    let current = camera.target.and_then(|target| ranking.iter().position(|entity| *entity == target));
+    // the followed player died or left: follow the leader
+    if current.is_none() {
+        camera.target = ranking.first().copied();
+    }
+    if action.just_pressed(&LocalInput::FreeCamera) {
+        camera.free = !camera.free;
+    }
+    if !ranking.is_empty() {
+        let current = current.unwrap_or(0);
+        if action.just_pressed(&LocalInput::NextTarget) {
+            camera.target = Some(ranking[(current + 1) % ranking.len()]);
+            camera.free = false;
+        }
+        if action.just_pressed(&LocalInput::PreviousTarget) {
+            camera.target = Some(ranking[(current + ranking.len() - 1) % ranking.len()]);
+            camera.free = false;
+        }
+    }
+    // the camera follows the focus target (see `follow_camera`)
+    let focus = camera.target
+        .filter(|_| !camera.free)
+        .and_then(|target| interpolated_snake(target, &players, &snakes));
+    match focus {
+        Some(snake) => commands.entity(player_entity).insert(FocusTarget(snake)),
+        None => commands.entity(player_entity).remove::<FocusTarget>(),
+    };
+}
+
+fn move_free_camera(
+    time: Res<Time>,
+    camera: Res<SpectatorCamera>,
+    action: Query<&ActionState<LocalInput>, With<Owned>>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), With<Camera>>,
+) {
+    if !camera.free {
+        return;
+    }
+    let (Ok(action), Ok((mut transform, projection))) = (action.get_single(), camera_query.get_single_mut()) else {
+        return;
+    };
+    let directions = [
+        (LocalInput::CameraUp, Vec2::Y),
+        (LocalInput::CameraDown, Vec2::NEG_Y),
+        (LocalInput::CameraLeft, Vec2::NEG_X),
+        (LocalInput::CameraRight, Vec2::X),
+    ];
+    let movement: Vec2 = directions.iter()
+        .filter(|(input, _)| action.pressed(input))
+        .map(|(_, direction)| *direction)
+        .sum();
+    transform.translation += (movement * FREE_CAMERA_SPEED * projection.scale * time.delta_seconds()).extend(0.0);
+}
@@ -0,0 +1,182 @@
+//! Headless load test: open many client connections to a server, and steer their snakes with bots.
+//!
+//! The clients either connect to an external server over UDP, or to a server running in the same process
+//! (in which case we also measure how long each server tick takes).
+//! Every `report_interval` seconds we print the RTT, bandwidth and rollbacks of each connection.
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy_turborand::prelude::*;
+use clap::{Parser, ValueEnum};
+use lightyear::prelude::{IoConfig, TransportConfig};
+use lightyear::prelude::client::ClientConnection;
+
+use client::NetworkStats;
+use integration::{press, release, Stepper, toggle_spawn};
+use shared::network::config::FIXED_TIMESTEP_HZ;
+use shared::network::protocol::PlayerMovement;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum LoadTestTransport {
+    /// run the server in the same process, and connect the clients through in-memory channels
+    InProcess,
+    /// connect to an external server over UDP
+    Udp,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum Steering {
+    /// turn in a random direction at random times
+    Random,
+    /// turn right, up, left, up... at a fixed interval
+    Scripted,
+}
+
+#[derive(Parser, PartialEq, Debug)]
+struct Cli {
+    /// Number of client connections
+    #[arg(short, long, default_value_t = 10)]
+    clients: usize,
+
+    #[arg(short, long, value_enum, default_value_t = LoadTestTransport::InProcess)]
+    transport: LoadTestTransport,
+
+    #[arg(long, default_value_t = Ipv4Addr::LOCALHOST)]
+    server_addr: Ipv4Addr,
+
+    #[arg(short, long, default_value_t = server::SERVER_PORT)]
+    server_port: u16,
+
+    #[arg(long, value_enum, default_value_t = Steering::Random)]
+    steering: Steering,
+
+    /// Duration of the load test, in seconds
+    #[arg(short, long, default_value_t = 60)]
+    duration: u64,
+
+    /// Interval between two reports, in seconds
+    #[arg(short, long, default_value_t = 5)]
+    report_interval: u64,
+}
+
+/// Probability that a random bot turns during a given tick
+const TURN_PROBABILITY: f64 = 0.05;
+/// Number of ticks between two turns of a scripted bot
+const SCRIPTED_TURN_INTERVAL: usize = 32;
+const SCRIPT: [PlayerMovement; 4] = [PlayerMovement::Right, PlayerMovement::Up, PlayerMovement::Left, PlayerMovement::Up];
+const DIRECTIONS: [PlayerMovement; 4] = [PlayerMovement::Up, PlayerMovement::Down, PlayerMovement::Left, PlayerMovement::Right];
+
+struct Bot {
+    rng: RngComponent,
+    pressed: Option<PlayerMovement>,
+    script_idx: usize,
+}
+
+impl Bot {
+    fn new(seed: u64) -> Self {
+        Self {
+            rng: RngComponent::with_seed(seed),
+            pressed: None,
+            script_idx: 0,
+        }
+    }
+
+    /// Choose the inputs of the bot for this frame, and apply them to the client app
+    fn steer(&mut self, world: &mut World, steering: Steering, frame: usize) {
+        let next = match steering {
+            Steering::Random => self.rng.chance(TURN_PROBABILITY).then(|| DIRECTIONS[self.rng.usize(0..DIRECTIONS.len())]),
+            Steering::Scripted => (frame % SCRIPTED_TURN_INTERVAL == 0).then(|| {
+                self.script_idx = (self.script_idx + 1) % SCRIPT.len();
+                SCRIPT[self.script_idx]
+            }),
+        };
+        let Some(next) = next else {
+            return;
+        };
+        if let Some(previous) = self.pressed.take() {
+            release(world, previous);
+        }
+        if press(world, next) {
+            self.pressed = Some(next);
+        } else {
+            // we don't have a snake: respawn
+            toggle_spawn(world);
+        }
+    }
+}
+
+/// Time spent in the server's updates
+#[derive(Default)]
+struct ServerTickTimes {
+    total: Duration,
+    max: Duration,
+    count: u32,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let frame_duration = Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ);
+    let (mut server_app, mut client_apps) = match cli.transport {
+        LoadTestTransport::InProcess => {
+            let stepper = Stepper::new(cli.clients);
+            (Some(stepper.server_app), stepper.client_apps)
+        }
+        LoadTestTransport::Udp => {
+            let server_addr = SocketAddr::new(cli.server_addr.into(), cli.server_port);
+            let client_apps = (0..cli.clients).map(|i| {
+                // let the OS pick the port of each client
+                let client_addr = SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0);
+                let io = IoConfig::from_transport(TransportConfig::UdpSocket(client_addr));
+                client::local_app(Stepper::client_id(i), server_addr, io, false)
+            }).collect();
+            (None, client_apps)
+        }
+    };
+    let mut bots: Vec<Bot> = (0..cli.clients).map(|i| Bot::new(i as u64)).collect();
+    let mut server_times = ServerTickTimes::default();
+
+    let total_frames = (cli.duration as f64 * FIXED_TIMESTEP_HZ) as usize;
+    let report_frames = ((cli.report_interval as f64 * FIXED_TIMESTEP_HZ) as usize).max(1);
+    for frame in 0..total_frames {
+        let frame_start = Instant::now();
+        if let Some(server_app) = server_app.as_mut() {
+            server_app.update();
+            let elapsed = frame_start.elapsed();
+            server_times.total += elapsed;
+            server_times.max = server_times.max.max(elapsed);
+            server_times.count += 1;
+        }
+        for (client_app, bot) in client_apps.iter_mut().zip(bots.iter_mut()) {
+            bot.steer(&mut client_app.world, cli.steering, frame);
+            client_app.update();
+        }
+        if frame > 0 && frame % report_frames == 0 {
+            report(&client_apps, &mut server_times, frame_duration);
+        }
+        // over UDP the apps run in real time
+        if server_app.is_none() {
+            std::thread::sleep(frame_duration.saturating_sub(frame_start.elapsed()));
+        }
+    }
+}
+
+fn report(client_apps: &[App], server_times: &mut ServerTickTimes, frame_duration: Duration) {
+    println!("{:>6} {:>10} {:>8} {:>8} {:>10} {:>10} {:>10}", "client", "connected", "rtt", "jitter", "in (B/s)", "out (B/s)", "rollbacks");
+    for (i, client_app) in client_apps.iter().enumerate() {
+        let connected = client_app.world.resource::<ClientConnection>().is_connected();
+        let stats = client_app.world.resource::<NetworkStats>();
+        println!(
+            "{:>6} {:>10} {:>8.1?} {:>8.1?} {:>10.0} {:>10.0} {:>10}",
+            Stepper::client_id(i), connected, stats.rtt, stats.jitter, stats.bytes_in, stats.bytes_out, stats.rollbacks,
+        );
+    }
+    if server_times.count > 0 {
+        let average = server_times.total / server_times.count;
+        println!(
+            "server tick: average {average:.2?}, max {:.2?}, budget {frame_duration:.2?}",
+            server_times.max,
+        );
+        *server_times = ServerTickTimes::default();
+    }
+}
@@ -0,0 +1,218 @@
+//! Run the server and N clients in the same process, communicating through in-memory channels.
+//! All the apps advance their time manually by one tick per frame, so that the tests are deterministic.
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use leafwing_input_manager::prelude::{ActionState, InputMap};
+use lightyear::prelude::{ClientId, IoConfig, TransportConfig};
+use lightyear::prelude::client::{ClientConnection, Confirmed, Interpolated, Predicted};
+
+use server::ServerSettings;
+use shared::network::config::FIXED_TIMESTEP_HZ;
+use shared::network::protocol::{DeadGameAction, PlayerMovement};
+use shared::network::protocol::prelude::*;
+
+/// Maximum number of frames to wait for the clients to connect
+pub const MAX_CONNECTION_FRAMES: usize = 200;
+
+pub struct Stepper {
+    pub server_app: App,
+    pub client_apps: Vec<App>,
+    pub frame_duration: Duration,
+}
+
+impl Stepper {
+    pub fn new(num_clients: usize) -> Self {
+        Self::with_settings(ServerSettings::default(), &vec![false; num_clients])
+    }
+
+    /// `spectate` has one entry per client: true if the client joins as a spectator
+    pub fn with_settings(settings: ServerSettings, spectate: &[bool]) -> Self {
+        // one frame is exactly one tick
+        let frame_duration = Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ);
+        let server_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+
+        let mut server_channels = vec![];
+        let mut client_apps = vec![];
+        for (i, &spectate) in spectate.iter().enumerate() {
+            // the client address is only used by the server to know which client sent a packet
+            let client_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), i as u16 + 1);
+            let (from_server_send, from_server_recv) = crossbeam_channel::unbounded();
+            let (to_server_send, to_server_recv) = crossbeam_channel::unbounded();
+            server_channels.push((client_addr, to_server_recv, from_server_send));
+
+            let client_io = IoConfig::from_transport(TransportConfig::LocalChannel {
+                recv: from_server_recv,
+                send: to_server_send,
+            });
+            let mut client_app = client::local_app(Self::client_id(i), server_addr, client_io, spectate);
+            client_app.insert_resource(TimeUpdateStrategy::ManualDuration(frame_duration));
+            client_apps.push(client_app);
+        }
+
+        let server_io = IoConfig::from_transport(TransportConfig::Channels {
+            channels: server_channels,
+        });
+        let mut server_app = server::local_app(server_io, settings);
+        server_app.insert_resource(TimeUpdateStrategy::ManualDuration(frame_duration));
+
+        Self {
+            server_app,
+            client_apps,
+            frame_duration,
+        }
+    }
+
+    /// Client id of the i-th client
+    pub fn client_id(i: usize) -> ClientId {
+        i as ClientId + 1
+    }
+
+    /// Run the startup systems and wait until all the clients are connected
+    pub fn init(&mut self) {
+        for _ in 0..MAX_CONNECTION_FRAMES {
+            self.frame_step();
+            if self.client_apps.iter().all(|app| app.world.resource::<ClientConnection>().is_connected()) {
+                return;
+            }
+        }
+        panic!("clients did not connect after {MAX_CONNECTION_FRAMES} frames");
+    }
+
+    /// Advance the server and all the clients by one frame (one tick)
+    pub fn frame_step(&mut self) {
+        self.server_app.update();
+        for client_app in self.client_apps.iter_mut() {
+            client_app.update();
+        }
+    }
+
+    pub fn advance_ticks(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.frame_step();
+        }
+    }
+
+    /// Step until `condition` is true on the world of every client, and panic after `max_frames` frames
+    pub fn step_until(&mut self, max_frames: usize, mut condition: impl FnMut(&mut World) -> bool) {
+        for _ in 0..max_frames {
+            self.frame_step();
+            if self.client_apps.iter_mut().all(|app| condition(&mut app.world)) {
+                return;
+            }
+        }
+        panic!("condition not met after {max_frames} frames");
+    }
+
+    /// Step until `condition` is true on the world of the server, and panic after `max_frames` frames
+    pub fn step_until_server(&mut self, max_frames: usize, mut condition: impl FnMut(&mut World) -> bool) {
+        for _ in 0..max_frames {
+            self.frame_step();
+            if condition(&mut self.server_app.world) {
+                return;
+            }
+        }
+        panic!("server condition not met after {max_frames} frames");
+    }
+
+    /// Close the connection of the i-th client, as if its transport was lost.
+    /// The client reconnects on its own and resumes its session
+    pub fn disconnect(&mut self, client: usize) {
+        let mut net = self.client_apps[client].world.resource_mut::<ClientConnection>();
+        net.disconnect().expect("failed to disconnect the client");
+    }
+
+    /// Press a movement key for the predicted snake of the i-th client. The key stays pressed until it is released
+    pub fn press(&mut self, client: usize, movement: PlayerMovement) {
+        assert!(press(&mut self.client_apps[client].world, movement), "the client does not have a predicted snake");
+    }
+
+    pub fn release(&mut self, client: usize, movement: PlayerMovement) {
+        assert!(release(&mut self.client_apps[client].world, movement), "the client does not have a predicted snake");
+    }
+
+    /// Tail of the snake of the i-th client on the server
+    pub fn server_tail(&mut self, client: usize) -> TailPoints {
+        let snake = self.server_snake(client).expect("the client does not have a snake on the server");
+        self.server_app.world.get::<TailPoints>(snake).unwrap().clone()
+    }
+
+    /// Snake entity of the i-th client on the server, if it has one
+    pub fn server_snake(&mut self, client: usize) -> Option<Entity> {
+        server_snake(&mut self.server_app.world, Self::client_id(client))
+    }
+
+    /// Confirmed tails that the i-th client received
+    pub fn confirmed_tails(&mut self, client: usize) -> Vec<TailPoints> {
+        Self::tails::<With<Confirmed>>(&mut self.client_apps[client].world)
+    }
+
+    /// Predicted tails of the i-th client (its own snake)
+    pub fn predicted_tails(&mut self, client: usize) -> Vec<TailPoints> {
+        Self::tails::<With<Predicted>>(&mut self.client_apps[client].world)
+    }
+
+    /// Interpolated tails of the i-th client (the snakes of the other clients)
+    pub fn interpolated_tails(&mut self, client: usize) -> Vec<TailPoints> {
+        Self::tails::<With<Interpolated>>(&mut self.client_apps[client].world)
+    }
+
+    fn tails<F: bevy::ecs::query::QueryFilter>(world: &mut World) -> Vec<TailPoints> {
+        world.query_filtered::<&TailPoints, F>().iter(world).cloned().collect()
+    }
+}
+
+/// Snake entity of the client `client_id` in the world of the server
+pub fn server_snake(world: &mut World, client_id: ClientId) -> Option<Entity> {
+    world.query::<(Entity, &HasPlayer)>()
+        .iter(world)
+        .find(|(_, has_player)| world.get::<Player>(has_player.0).is_some_and(|p| p.id == client_id))
+        .map(|(entity, _)| entity)
+}
+
+/// Press a movement key for the predicted snake of a client app.
+/// Returns false if the client does not have a predicted snake (for example because it is dead)
+pub fn press(world: &mut World, movement: PlayerMovement) -> bool {
+    with_predicted_action(world, |action| action.press(&movement))
+}
+
+pub fn release(world: &mut World, movement: PlayerMovement) -> bool {
+    with_predicted_action(world, |action| action.release(&movement))
+}
+
+fn with_predicted_action(world: &mut World, f: impl FnOnce(&mut ActionState<PlayerMovement>)) -> bool {
+    let Ok(entity) = world.query_filtered::<Entity, (With<Predicted>, With<ActionState<PlayerMovement>>)>()
+        .get_single(world) else {
+        return false;
+    };
+    // remove the keyboard bindings so that the injected inputs are not overwritten by the keyboard state
+    let mut entity_mut = world.entity_mut(entity);
+    entity_mut.remove::<InputMap<PlayerMovement>>();
+    f(&mut entity_mut.get_mut::<ActionState<PlayerMovement>>().unwrap());
+    true
+}
+
+/// Toggle the spawn action of the local player of a client app. The action needs to be released
+/// before it can be pressed again, so this needs to be called on two consecutive frames to respawn
+pub fn toggle_spawn(world: &mut World) {
+    let Ok(entity) = world.query_filtered::<Entity, With<ActionState<DeadGameAction>>>().get_single(world) else {
+        return;
+    };
+    let mut entity_mut = world.entity_mut(entity);
+    entity_mut.remove::<InputMap<DeadGameAction>>();
+    let mut action = entity_mut.get_mut::<ActionState<DeadGameAction>>().unwrap();
+    if action.pressed(&DeadGameAction::Spawn) {
+        action.release(&DeadGameAction::Spawn);
+    } else {
+        action.press(&DeadGameAction::Spawn);
+    }
+}
+
+/// Inflection points of a tail (all the points except the head and the end of the tail).
+/// They do not move once created, so they can be compared between tails that are seen at different ticks
+pub fn turn_points(tail: &TailPoints) -> Vec<Vec2> {
+    let len = tail.0.len();
+    tail.0.iter().skip(1).take(len.saturating_sub(2)).map(|(pos, _)| *pos).collect()
+}
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+use lightyear::prelude::client::{ClientConnection, Predicted};
+
+use client::CurrentLink;
+use integration::Stepper;
+use shared::network::config::LinkSettings;
+use shared::network::protocol::prelude::*;
+
+const MAX_FRAMES: usize = 200;
+
+fn predicted_snake_spawned(world: &mut World) -> bool {
+    world.query_filtered::<(), (With<TailPoints>, With<Predicted>)>().iter(world).next().is_some()
+}
+
+fn connected(world: &mut World) -> bool {
+    world.resource::<ClientConnection>().is_connected()
+}
+
+fn set_link(stepper: &mut Stepper, settings: LinkSettings) {
+    stepper.client_apps[0].world.resource_mut::<CurrentLink>().settings = settings;
+}
+
+#[test]
+fn test_link_change_reconnects() {
+    let mut stepper = Stepper::new(1);
+    stepper.init();
+    stepper.step_until(MAX_FRAMES, predicted_snake_spawned);
+
+    // changing the link disconnects, and we reconnect with the new conditioner
+    set_link(&mut stepper, LinkSettings::default());
+    stepper.step_until(MAX_FRAMES, |world| !connected(world));
+    stepper.step_until(MAX_FRAMES, connected);
+}
+
+#[test]
+fn test_link_change_applies_new_settings() {
+    let mut stepper = Stepper::new(1);
+    stepper.init();
+    stepper.step_until(MAX_FRAMES, predicted_snake_spawned);
+
+    // with a conditioner that drops every packet, the client cannot connect again: this only happens if the
+    // new connection uses the new settings
+    set_link(&mut stepper, LinkSettings {
+        loss: 1.0,
+        ..default()
+    });
+    stepper.step_until(MAX_FRAMES, |world| !connected(world));
+    stepper.advance_ticks(MAX_FRAMES);
+    assert!(!connected(&mut stepper.client_apps[0].world));
+}
@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use lightyear::prelude::client::Predicted;
+
+use integration::{Stepper, turn_points};
+use shared::network::protocol::PlayerMovement;
+use shared::network::protocol::prelude::*;
+
+const MAX_FRAMES: usize = 200;
+/// Number of ticks we wait after the last input, so that every client received the latest server state
+/// (the clients do not have any latency, so this only needs to cover the interpolation delay)
+const SETTLE_TICKS: usize = 40;
+
+fn predicted_snake_spawned(world: &mut World) -> bool {
+    world.query_filtered::<(), (With<TailPoints>, With<Predicted>)>().iter(world).next().is_some()
+}
+
+#[test]
+fn test_tails_converge() {
+    let mut stepper = Stepper::new(2);
+    stepper.init();
+    stepper.step_until(MAX_FRAMES, predicted_snake_spawned);
+    // wait until each client also received the snake of the other client
+    stepper.step_until(MAX_FRAMES, |world| {
+        world.query::<&TailPoints>().iter(world).count() == 4
+    });
+
+    // client 0 turns right, then up again
+    stepper.press(0, PlayerMovement::Right);
+    stepper.advance_ticks(5);
+    stepper.release(0, PlayerMovement::Right);
+    stepper.press(0, PlayerMovement::Up);
+    stepper.advance_ticks(5);
+    stepper.release(0, PlayerMovement::Up);
+    stepper.advance_ticks(SETTLE_TICKS);
+
+    let server_tail = stepper.server_tail(0);
+    let expected = turn_points(&server_tail);
+    assert_eq!(expected.len(), 2, "the snake should have turned twice on the server");
+
+    // client 0: the predicted and confirmed snakes made the same turns as on the server
+    let predicted = stepper.predicted_tails(0);
+    assert_eq!(predicted.len(), 1);
+    assert_eq!(turn_points(&predicted[0]), expected);
+    assert_eq!(predicted[0].front().1, server_tail.front().1);
+    let confirmed = stepper.confirmed_tails(0);
+    assert!(confirmed.iter().any(|tail| turn_points(tail) == expected));
+
+    // client 1: the interpolated snake of client 0 made the same turns as on the server
+    let interpolated = stepper.interpolated_tails(1);
+    assert_eq!(interpolated.len(), 1);
+    assert_eq!(turn_points(&interpolated[0]), expected);
+    assert_eq!(interpolated[0].front().1, server_tail.front().1);
+}
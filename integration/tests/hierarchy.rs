@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use lightyear::prelude::client::Confirmed;
+
+use integration::Stepper;
+use shared::network::protocol::prelude::*;
+
+const MAX_FRAMES: usize = 200;
+
+fn confirmed_snake_replicated(world: &mut World) -> bool {
+    world.query_filtered::<(), (With<HasPlayer>, With<Confirmed>)>().iter(world).next().is_some()
+}
+
+#[test]
+fn test_snake_is_child_of_player() {
+    let mut stepper = Stepper::new(1);
+    stepper.init();
+    stepper.step_until(MAX_FRAMES, confirmed_snake_replicated);
+    // one more frame so that the hierarchy is updated on the client
+    stepper.frame_step();
+
+    // server: the snake is a child of the player
+    let client_id = Stepper::client_id(0);
+    let server_world = &mut stepper.server_app.world;
+    let (server_has_player, server_parent) = server_world.query::<(&HasPlayer, &Parent)>().single(server_world);
+    assert_eq!(server_has_player.0, server_parent.get());
+    assert_eq!(server_world.get::<Player>(server_parent.get()).unwrap().id, client_id);
+
+    // client: the replicated HasPlayer points to the replicated player, and the snake is a child of that player
+    let client_world = &mut stepper.client_apps[0].world;
+    let (has_player, parent) = client_world.query_filtered::<(&HasPlayer, &Parent), With<Confirmed>>()
+        .single(client_world);
+    assert_eq!(has_player.0, parent.get());
+    assert_eq!(client_world.get::<Player>(has_player.0).unwrap().id, client_id);
+}
+
+#[test]
+fn test_despawn_player_despawns_snake() {
+    let mut stepper = Stepper::new(1);
+    stepper.init();
+    stepper.step_until(MAX_FRAMES, confirmed_snake_replicated);
+    stepper.frame_step();
+
+    // despawn the player on the server: its snake should be despawned on the server and on the client
+    let server_world = &mut stepper.server_app.world;
+    let player = server_world.query_filtered::<Entity, With<Player>>().single(server_world);
+    server_world.entity_mut(player).despawn_recursive();
+    assert_eq!(server_world.query::<&TailPoints>().iter(server_world).count(), 0);
+
+    stepper.step_until(MAX_FRAMES, |world| {
+        world.query::<&Player>().iter(world).next().is_none()
+            && world.query::<&TailPoints>().iter(world).next().is_none()
+    });
+}
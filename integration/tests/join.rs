@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::prelude::client::{ClientConnection, Predicted};
+
+use integration::{server_snake, Stepper};
+use server::{BotSettings, PlayerCap, ServerSettings};
+use shared::network::protocol::DeadGameAction;
+use shared::network::protocol::prelude::*;
+
+const MAX_FRAMES: usize = 200;
+/// Number of ticks we wait so that the server handled all the messages of the clients (`Join`, `ResumeSession`)
+const SETTLE_TICKS: usize = 40;
+
+fn count<F: bevy::ecs::query::QueryFilter>(world: &mut World) -> usize {
+    world.query_filtered::<(), F>().iter(world).count()
+}
+
+#[test]
+fn test_spectator_join() {
+    let mut stepper = Stepper::with_settings(ServerSettings::default(), &[true]);
+    stepper.init();
+    stepper.step_until_server(MAX_FRAMES, |world| count::<With<Spectator>>(world) == 1);
+    // wait a bit to make sure that no snake gets spawned afterwards
+    stepper.advance_ticks(SETTLE_TICKS);
+
+    let server_world = &mut stepper.server_app.world;
+    let player = server_world.query_filtered::<Entity, With<Spectator>>().single(server_world);
+    assert!(server_world.get::<ActionState<DeadGameAction>>(player).is_none());
+    assert_eq!(count::<With<TailPoints>>(server_world), 0);
+    let client_world = &mut stepper.client_apps[0].world;
+    assert_eq!(count::<(With<TailPoints>, With<Predicted>)>(client_world), 0);
+}
+
+#[test]
+fn test_cap_overflow_spectates() {
+    let mut stepper = Stepper::with_settings(ServerSettings {
+        player_cap: PlayerCap(1),
+        ..default()
+    }, &[false, false]);
+    stepper.init();
+    stepper.step_until_server(MAX_FRAMES, |world| {
+        count::<With<TailPoints>>(world) == 1 && count::<With<Spectator>>(world) == 1
+    });
+    stepper.advance_ticks(SETTLE_TICKS);
+
+    // one client got the only slot, the other one watches
+    let server_world = &mut stepper.server_app.world;
+    assert_eq!(count::<With<TailPoints>>(server_world), 1);
+    let spectator = server_world.query_filtered::<Entity, With<Spectator>>().single(server_world);
+    assert!(server_world.get::<ActionState<DeadGameAction>>(spectator).is_none());
+}
+
+#[test]
+fn test_bots_and_spectators_not_counted() {
+    let mut stepper = Stepper::with_settings(ServerSettings {
+        player_cap: PlayerCap(1),
+        bot_settings: BotSettings {
+            min_population: 1,
+            ..default()
+        },
+        ..default()
+    }, &[true, false]);
+    // run the server alone until it added a bot
+    for _ in 0..MAX_FRAMES {
+        stepper.server_app.update();
+        if count::<With<Player>>(&mut stepper.server_app.world) > 0 {
+            break;
+        }
+    }
+    assert_eq!(count::<With<Player>>(&mut stepper.server_app.world), 1, "the server should have added a bot");
+
+    // the player cap is reached only if we count the bot or the spectator
+    stepper.init();
+    let client_id = Stepper::client_id(1);
+    stepper.step_until_server(MAX_FRAMES, |world| {
+        count::<With<Spectator>>(world) == 1 && server_snake(world, client_id).is_some()
+    });
+    assert!(stepper.server_snake(0).is_none(), "the spectator should not have a snake");
+}
+
+#[test]
+fn test_join_after_resume_is_ignored() {
+    let mut stepper = Stepper::new(1);
+    stepper.init();
+    stepper.step_until(MAX_FRAMES, |world| count::<(With<TailPoints>, With<Predicted>)>(world) > 0);
+
+    // the client sends `ResumeSession` and then `Join` when it reconnects
+    stepper.disconnect(0);
+    stepper.step_until(MAX_FRAMES, |world| !world.resource::<ClientConnection>().is_connected());
+    stepper.step_until(MAX_FRAMES, |world| world.resource::<ClientConnection>().is_connected());
+    stepper.advance_ticks(SETTLE_TICKS);
+
+    // the resumed player did not get a second snake, and the player created for the new connection is gone
+    let server_world = &mut stepper.server_app.world;
+    assert_eq!(count::<With<Player>>(server_world), 1);
+    assert_eq!(count::<With<TailPoints>>(server_world), 1);
+    assert_eq!(count::<With<Spectator>>(server_world), 0);
+}
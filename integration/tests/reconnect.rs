@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use lightyear::prelude::client::{ClientConnection, Predicted};
+
+use integration::Stepper;
+use server::{GraceMode, Invulnerable, ReconnectPolicy, ServerSettings};
+use shared::movement::Frozen;
+use shared::network::protocol::prelude::*;
+
+const MAX_FRAMES: usize = 200;
+/// Number of ticks we wait after reconnecting, so that the server resumed the session and the client received it
+const SETTLE_TICKS: usize = 40;
+
+fn predicted_snake_spawned(world: &mut World) -> bool {
+    world.query_filtered::<(), (With<TailPoints>, With<Predicted>)>().iter(world).next().is_some()
+}
+
+fn connected(world: &mut World) -> bool {
+    world.resource::<ClientConnection>().is_connected()
+}
+
+/// Player of the predicted snake of the client
+fn predicted_snake_owner(world: &mut World) -> Option<u64> {
+    let has_player = world.query_filtered::<&HasPlayer, With<Predicted>>().get_single(world).ok()?.0;
+    world.get::<Player>(has_player).map(|player| player.id)
+}
+
+#[test]
+fn test_resume_session_keeps_snake() {
+    let mut stepper = Stepper::new(1);
+    stepper.init();
+    stepper.step_until(MAX_FRAMES, predicted_snake_spawned);
+    let snake = stepper.server_snake(0).unwrap();
+
+    stepper.disconnect(0);
+    stepper.step_until(MAX_FRAMES, |world| !connected(world));
+    // the client reconnects on its own, and sends its session token
+    stepper.step_until(MAX_FRAMES, connected);
+    stepper.advance_ticks(SETTLE_TICKS);
+
+    // the server gave back the same snake, and did not spawn another one for the new connection
+    assert_eq!(stepper.server_snake(0), Some(snake));
+    let server_world = &mut stepper.server_app.world;
+    assert_eq!(server_world.query::<&TailPoints>().iter(server_world).count(), 1);
+    // the client predicts its snake again
+    let client_world = &mut stepper.client_apps[0].world;
+    assert_eq!(client_world.query_filtered::<(), (With<TailPoints>, With<Predicted>)>().iter(client_world).count(), 1);
+    assert_eq!(predicted_snake_owner(client_world), Some(Stepper::client_id(0)));
+}
+
+#[test]
+fn test_frozen_grace_mode() {
+    let mut stepper = Stepper::with_settings(ServerSettings {
+        reconnect_policy: ReconnectPolicy {
+            grace_period: Duration::from_secs(10),
+            mode: GraceMode::Frozen,
+        },
+        ..default()
+    }, &[false]);
+    stepper.init();
+    stepper.step_until(MAX_FRAMES, predicted_snake_spawned);
+    let snake = stepper.server_snake(0).unwrap();
+
+    // during the grace period the snake cannot move or die
+    stepper.disconnect(0);
+    stepper.step_until_server(MAX_FRAMES, |world| {
+        world.get::<Frozen>(snake).is_some() && world.get::<Invulnerable>(snake).is_some()
+    });
+
+    // the snake is back to normal once the session is resumed
+    stepper.step_until(MAX_FRAMES, connected);
+    stepper.advance_ticks(SETTLE_TICKS);
+    assert_eq!(stepper.server_snake(0), Some(snake));
+    let server_world = &stepper.server_app.world;
+    assert!(server_world.get::<Frozen>(snake).is_none());
+    assert!(server_world.get::<Invulnerable>(snake).is_none());
+}
@@ -0,0 +1,142 @@
+//! Choose the look of each snake when it spawns.
+//!
+//! Clients can send a `SkinPreference` when they connect. When a snake spawns, the server keeps the preferred pattern
+//! and head style of its player, and picks a hue that is far from the hues of the snakes nearby (the preferred hue
+//! is used if it doesn't clash). The result is stored in the replicated `SnakeAppearance` of the player.
+//! The preference can arrive after the snake spawned, so the appearance is assigned again when it is received.
+use bevy::prelude::*;
+use bevy_turborand::prelude::*;
+use lightyear::server::events::MessageEvent;
+
+use shared::network::protocol::prelude::*;
+
+use crate::network::connection_events::Global;
+
+/// Snakes whose head is closer than this are considered nearby
+const NEARBY_DISTANCE: f32 = 800.0;
+/// Minimum difference of hue (in degrees) between two nearby snakes for the preferred hue to be used
+const MIN_HUE_DISTANCE: f32 = 30.0;
+/// Number of hues that we consider when the preferred one clashes
+const HUE_CANDIDATES: usize = 36;
+
+/// Skin requested by the client of this player
+#[derive(Component, Debug)]
+pub(crate) struct PreferredSkin(SkinPreference);
+
+pub(crate) struct AppearancePlugin;
+
+impl Plugin for AppearancePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (receive_skin_preferences, assign_appearance).chain());
+    }
+}
+
+fn receive_skin_preferences(
+    global: Res<Global>,
+    mut messages: EventReader<MessageEvent<SkinPreference>>,
+    mut commands: Commands,
+) {
+    for message in messages.read() {
+        let client_id = message.context();
+        if let Some(&player_entity) = global.client_id_map.get(client_id) {
+            debug!(?client_id, preference = ?message.message(), "Received skin preference");
+            let mut preference = message.message().clone();
+            if preference.hue.is_some_and(|hue| !hue.is_finite()) {
+                warn!(?client_id, hue = ?preference.hue, "Ignoring invalid preferred hue");
+                preference.hue = None;
+            }
+            commands.entity(player_entity).insert(PreferredSkin(preference));
+        }
+    }
+}
+
+/// Circular distance between two hues, in degrees
+fn hue_distance(a: f32, b: f32) -> f32 {
+    let diff = (a - b).rem_euclid(360.0);
+    diff.min(360.0 - diff)
+}
+
+/// Pick the preferred hue if it is far enough from the `nearby` hues, otherwise the candidate hue that is the
+/// furthest from them. `offset` (in [0, 1)) rotates the candidates, so that ties are not always broken the same way
+fn pick_hue(preferred: Option<f32>, nearby: &[f32], offset: f32) -> f32 {
+    let min_distance = |hue: f32| nearby.iter().map(|other| hue_distance(hue, *other)).fold(180.0, f32::min);
+    if let Some(hue) = preferred.filter(|hue| hue.is_finite() && min_distance(*hue) >= MIN_HUE_DISTANCE) {
+        return hue.rem_euclid(360.0);
+    }
+    let step = 360.0 / HUE_CANDIDATES as f32;
+    (0..HUE_CANDIDATES)
+        .map(|i| (i as f32 + offset) * step)
+        .fold((0.0, -1.0), |(best, best_distance), hue| {
+            let distance = min_distance(hue);
+            if distance > best_distance { (hue, distance) } else { (best, best_distance) }
+        }).0
+}
+
+/// Assign an appearance to the new snakes, and to the snakes whose player just sent a preference
+fn assign_appearance(
+    mut commands: Commands,
+    mut rng: ResMut<GlobalRng>,
+    new_snakes: Query<(&HasPlayer, &TailPoints), Added<HasPlayer>>,
+    new_preferences: Query<&Children, Changed<PreferredSkin>>,
+    snakes: Query<(&HasPlayer, &TailPoints)>,
+    players: Query<(Option<&PreferredSkin>, Option<&SnakeAppearance>), With<Player>>,
+) {
+    let mut to_assign: Vec<(&HasPlayer, &TailPoints)> = new_snakes.iter().collect();
+    for children in new_preferences.iter() {
+        for snake in children.iter().filter_map(|child| snakes.get(*child).ok()) {
+            if !to_assign.iter().any(|(has_player, _)| has_player.0 == snake.0.0) {
+                to_assign.push(snake);
+            }
+        }
+    }
+    // appearances assigned during this frame, which are not in the players' components yet
+    let mut assigned: Vec<(Entity, Vec2, f32)> = vec![];
+    for (has_player, tail) in to_assign {
+        let Ok((preferred, _)) = players.get(has_player.0) else {
+            continue;
+        };
+        let head = tail.front().0;
+        let nearby: Vec<f32> = snakes.iter()
+            .filter_map(|(other, other_tail)| {
+                let (_, appearance) = players.get(other.0).ok()?;
+                Some((other.0, other_tail.front().0, appearance?.hue))
+            })
+            .chain(assigned.iter().copied())
+            .filter(|(player, pos, _)| *player != has_player.0 && pos.distance(head) < NEARBY_DISTANCE)
+            .map(|(_, _, hue)| hue)
+            .collect();
+        let preferred = preferred.map(|skin| &skin.0);
+        let appearance = SnakeAppearance {
+            hue: pick_hue(preferred.and_then(|skin| skin.hue), &nearby, rng.f32()),
+            // players without a preference (for example bots) get a random skin
+            pattern: preferred.map_or_else(
+                || [SnakePattern::Solid, SnakePattern::Striped, SnakePattern::Dotted][rng.usize(0..3)],
+                |skin| skin.pattern,
+            ),
+            head: preferred.map_or_else(
+                || [HeadStyle::Arrow, HeadStyle::Round, HeadStyle::Square][rng.usize(0..3)],
+                |skin| skin.head,
+            ),
+        };
+        assigned.push((has_player.0, head, appearance.hue));
+        commands.entity(has_player.0).insert(appearance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_hue() {
+        // the preferred hue is used if it doesn't clash
+        assert_eq!(pick_hue(Some(100.0), &[0.0, 200.0], 0.0), 100.0);
+        // otherwise we pick the hue furthest from the nearby snakes
+        let hue = pick_hue(Some(5.0), &[0.0, 120.0, 240.0], 0.0);
+        assert!([60.0, 180.0, 300.0].iter().any(|expected| hue_distance(hue, *expected) < 1e-3));
+        // invalid hues are ignored
+        assert!(pick_hue(Some(f32::NAN), &[], 0.0).is_finite());
+        // the distance wraps around
+        assert_eq!(hue_distance(350.0, 10.0), 20.0);
+    }
+}
@@ -0,0 +1,273 @@
+//! Snakes controlled by the server.
+//!
+//! A bot is a `Player` entity with a `Bot` component. Its snake is a normal snake (with an `ActionState<PlayerMovement>`)
+//! whose inputs are written by the AI instead of being received from a client:
+//! - it casts rays in front and on the sides of the head (like `snake_friction`) to avoid tails and walls
+//! - it turns towards the closest food
+//! - optionally, it tries to cut in front of the closest snake
+//!
+//! Bots are added and removed so that there are always at least `min_population` players.
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+use bevy_turborand::prelude::*;
+use bevy_xpbd_2d::prelude::{SpatialQuery, SpatialQueryFilter};
+use clap::ValueEnum;
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::prelude::ClientId;
+
+use shared::collision::layers::CollideLayer;
+use shared::map::{MapMarker, MapSize};
+use shared::movement::SimulationSet;
+use shared::network::bundle::player::PlayerBundle;
+use shared::network::bundle::snake::SnakeBundle;
+use shared::network::protocol::PlayerMovement;
+use shared::network::protocol::prelude::*;
+use shared::network::protocol::prelude::Direction;
+
+/// Bots use client ids starting from this value, so that they don't clash with the ids of real clients
+pub const BOT_CLIENT_ID_START: ClientId = 1 << 48;
+/// Interval at which we add or remove bots, and respawn the dead ones
+pub const BOT_POPULATION_INTERVAL: Duration = Duration::from_secs(1);
+/// Distance from the head at which the rays start, so that the bot doesn't see its own head
+const RAY_OFFSET: f32 = 0.01;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// Number of ticks between two decisions
+    fn reaction_ticks(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 16,
+            Difficulty::Medium => 8,
+            Difficulty::Hard => 2,
+        }
+    }
+
+    /// Distance at which obstacles are detected
+    fn look_ahead(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 20.0,
+            Difficulty::Medium => 40.0,
+            Difficulty::Hard => 80.0,
+        }
+    }
+
+    /// Distance at which food and other snakes are noticed
+    fn sight(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 100.0,
+            Difficulty::Medium => 200.0,
+            Difficulty::Hard => 400.0,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct BotSettings {
+    pub difficulty: Difficulty,
+    /// bots are added until there are at least this many players (humans and bots)
+    pub min_population: usize,
+    /// if true, the bots try to cut in front of other snakes
+    pub hunt: bool,
+}
+
+#[derive(Component, Debug)]
+pub struct Bot {
+    pub difficulty: Difficulty,
+    /// number of ticks until the next decision
+    cooldown: u32,
+}
+
+pub struct BotPlugin {
+    pub settings: BotSettings,
+}
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        // resources
+        app.insert_resource(self.settings.clone());
+        // systems
+        app.add_systems(FixedUpdate, steer_bots.before(SimulationSet::Movement));
+        app.add_systems(Update, (balance_population, respawn_bots).run_if(on_timer(BOT_POPULATION_INTERVAL)));
+    }
+}
+
+/// Add or remove bots so that the number of players is at least `min_population` (spectators are not counted)
+fn balance_population(
+    mut commands: Commands,
+    settings: Res<BotSettings>,
+    humans: Query<(), (With<Player>, Without<Bot>, Without<Spectator>)>,
+    bots: Query<(Entity, &Player), With<Bot>>,
+) {
+    let target = settings.min_population.saturating_sub(humans.iter().count());
+    let count = bots.iter().count();
+    if count < target {
+        // find unused bot ids
+        let mut next_id = BOT_CLIENT_ID_START;
+        for _ in count..target {
+            while bots.iter().any(|(_, player)| player.id == next_id) {
+                next_id += 1;
+            }
+            info!(id = ?next_id, "Adding bot");
+            commands.spawn((
+                PlayerBundle::new(Player {
+                    id: next_id,
+                    name: format!("Bot {}", next_id - BOT_CLIENT_ID_START),
+                }),
+                PlayerBundle::replicate(next_id),
+                Bot {
+                    difficulty: settings.difficulty,
+                    cooldown: 0,
+                },
+            ));
+            next_id += 1;
+        }
+    } else {
+        // the snake is a child of the bot, so it gets despawned as well
+        for (entity, player) in bots.iter().take(count - target) {
+            info!(id = ?player.id, "Removing bot");
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Spawn a snake for the bots that don't have one, at a random position on the map
+fn respawn_bots(
+    mut commands: Commands,
+    mut map: Query<(&MapSize, &mut RngComponent), With<MapMarker>>,
+    bots: Query<(Entity, &Player, Option<&Children>), With<Bot>>,
+) {
+    let Ok((map_size, mut rng)) = map.get_single_mut() else {
+        return;
+    };
+    for (bot_entity, player, children) in bots.iter() {
+        if children.is_some_and(|children| !children.is_empty()) {
+            continue;
+        }
+        let head = Vec2::new(
+            rng.f32_normalized() * map_size.width * 0.4,
+            rng.f32_normalized() * map_size.height * 0.4,
+        );
+        commands.spawn((
+            SnakeBundle::new(head),
+            SnakeBundle::replicate_server_owned(player.id),
+            HasPlayer(bot_entity),
+        ));
+    }
+}
+
+/// Distance that the head can travel in `direction` before hitting a tail or a wall, up to `max_distance`
+fn free_distance(
+    spatial_query: &SpatialQuery,
+    map_size: &MapSize,
+    head: Vec2,
+    direction: Direction,
+    max_distance: f32,
+) -> f32 {
+    let delta = direction.delta();
+    let filter = SpatialQueryFilter::from_mask([CollideLayer::Player, CollideLayer::Wall]);
+    let tail_distance = spatial_query.cast_ray(
+        head + delta * RAY_OFFSET,
+        Direction2d::new_unchecked(delta),
+        max_distance,
+        false,
+        filter,
+    ).map_or(max_distance, |hit| hit.time_of_impact);
+    // the walls are the edges of the map
+    let half_width = map_size.width / 2.0;
+    let half_height = map_size.height / 2.0;
+    let wall_distance = match direction {
+        Direction::Up => half_height - head.y,
+        Direction::Down => head.y + half_height,
+        Direction::Right => half_width - head.x,
+        Direction::Left => head.x + half_width,
+    };
+    tail_distance.min(wall_distance).max(0.0)
+}
+
+/// The two directions in which a snake going in `direction` can turn
+fn turns(direction: Direction) -> [Direction; 2] {
+    match direction {
+        Direction::Up | Direction::Down => [Direction::Left, Direction::Right],
+        Direction::Left | Direction::Right => [Direction::Up, Direction::Down],
+    }
+}
+
+/// Choose the inputs of each bot
+fn steer_bots(
+    settings: Res<BotSettings>,
+    spatial_query: SpatialQuery,
+    map: Query<&MapSize, With<MapMarker>>,
+    mut bots: Query<&mut Bot>,
+    mut snakes: Query<(Entity, &HasPlayer, &TailPoints, &mut ActionState<PlayerMovement>)>,
+    food: Query<&Position, With<FoodMarker>>,
+) {
+    let Ok(map_size) = map.get_single() else {
+        return;
+    };
+    // heads of all the snakes, used for hunting
+    let heads: Vec<(Entity, Vec2, Direction)> = snakes.iter()
+        .map(|(entity, _, tail, _)| (entity, tail.front().0, tail.front().1))
+        .collect();
+    for (entity, has_player, tail, mut action) in snakes.iter_mut() {
+        let Ok(mut bot) = bots.get_mut(has_player.0) else {
+            continue;
+        };
+        if bot.cooldown > 0 {
+            bot.cooldown -= 1;
+            continue;
+        }
+        bot.cooldown = bot.difficulty.reaction_ticks();
+        let (head, direction) = *tail.front();
+        let look_ahead = bot.difficulty.look_ahead();
+        let sight = bot.difficulty.sight();
+
+        // 1. avoid obstacles: if there is something in front of us, turn to the side with the most space
+        let [side_a, side_b] = turns(direction);
+        let free_a = free_distance(&spatial_query, map_size, head, side_a, look_ahead);
+        let free_b = free_distance(&spatial_query, map_size, head, side_b, look_ahead);
+        let best_side = if free_a >= free_b { side_a } else { side_b };
+        let target_direction = if free_distance(&spatial_query, map_size, head, direction, look_ahead) < look_ahead {
+            best_side
+        } else {
+            // 2. otherwise, go towards a target: a point in front of the closest snake if we hunt, or the closest food
+            let prey = settings.hunt.then(|| {
+                heads.iter()
+                    .filter(|(other, pos, _)| *other != entity && pos.distance(head) < sight)
+                    .min_by(|(_, a, _), (_, b, _)| a.distance(head).total_cmp(&b.distance(head)))
+                    .map(|(_, pos, dir)| *pos + dir.delta() * look_ahead)
+            }).flatten();
+            let target = prey.or_else(|| {
+                food.iter()
+                    .map(|pos| pos.0)
+                    .filter(|pos| pos.distance(head) < sight)
+                    .min_by(|a, b| a.distance(head).total_cmp(&b.distance(head)))
+            });
+            match target {
+                Some(target) => {
+                    // turn towards the target if it is on our side and the way is free
+                    let to_target = target - head;
+                    turns(direction).into_iter()
+                        .find(|side| side.delta().dot(to_target) > 0.0
+                            && direction.delta().dot(to_target) <= 0.0
+                            && free_distance(&spatial_query, map_size, head, *side, look_ahead) >= look_ahead)
+                        .unwrap_or(direction)
+                }
+                None => direction,
+            }
+        };
+
+        action.release_all();
+        if target_direction != direction {
+            action.press(&PlayerMovement::from(target_direction));
+        }
+    }
+}
@@ -22,6 +22,10 @@ impl Plugin for ColliderPlugin {
 }
 
 
+/// Snakes with this component cannot die
+#[derive(Component, Debug, Default)]
+pub struct Invulnerable;
+
 // NOTE: IMPORTANT
 // because we do the ray cast with a small offset, we need to make sure that the collision distance is big enough
 // that the snake cannot 'jump' over the obstable in one movement update
@@ -33,7 +37,7 @@ pub const COLLISION_DISTANCE: f32 = 1.0;
 
 pub(crate) fn snake_collisions(
     spatial_query: SpatialQuery,
-    tails: Query<(Entity, &TailPoints)>,
+    tails: Query<(Entity, &TailPoints), Without<Invulnerable>>,
     mut writer: EventWriter<SnakeCollision>,
 ) {
     for (entity, tail) in tails.iter() {
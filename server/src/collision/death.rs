@@ -9,8 +9,7 @@ pub struct DeathPlugin;
 
 impl Plugin for DeathPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, handle_collision.after(ColliderSet::ComputeCollision));
-        app.add_systems(Update, handle_collision.after(ColliderSet::ComputeCollision));
+        app.add_systems(Update, (handle_collision, send_food_collision).after(ColliderSet::ComputeCollision));
     }
 }
 
@@ -18,7 +17,7 @@ impl Plugin for DeathPlugin {
 pub fn handle_collision(
     mut reader: EventReader<SnakeCollision>,
     mut connection_manager: ResMut<ServerConnectionManager>,
-    mut players: Query<&mut Player>,
+    players: Query<(), With<Player>>,
     snakes: Query<&HasPlayer>,
     mut commands: Commands,
 ) {
@@ -31,10 +30,10 @@ pub fn handle_collision(
             error!("snake does not have HasPlayer component");
             continue;
         };
-        let Ok(mut killed) = players.get_mut(killed_player.0) else {
+        if players.get(killed_player.0).is_err() {
             error!("player could not be found");
             continue;
-        };
+        }
         info!(?collision_event, "Collision event!");
 
         // we are sending this message so that the client can render the kill effects
@@ -44,8 +43,20 @@ pub fn handle_collision(
             killed: killed_player.0,
         }, NetworkTarget::All).map_err(|e| error!(?e, "Failed to send message"));
 
-        // despawn dead snake and remove snake from player
+        // despawn dead snake (this also removes it from the player's children)
         commands.entity(collision_event.killed).despawn_recursive();
-        killed.snake = None;
     }
-}
\ No newline at end of file
+}
+
+/// Tell the clients who ate which food, so that they can animate the food flying to the snake before despawning it
+pub fn send_food_collision(
+    mut reader: EventReader<FoodCollision>,
+    mut connection_manager: ResMut<ServerConnectionManager>,
+) {
+    for collision_event in reader.read() {
+        let _ = connection_manager.send_message_to_target::<GameChannel, _>(
+            collision_event.clone(),
+            NetworkTarget::All,
+        ).map_err(|e| error!(?e, "Failed to send message"));
+    }
+}
@@ -1,8 +1,10 @@
 use bevy::prelude::*;
 
-mod collider;
+pub(crate) mod collider;
 mod death;
 
+pub use collider::Invulnerable;
+
 pub struct CollisionPlugin;
 
 impl Plugin for CollisionPlugin {
@@ -0,0 +1,65 @@
+//! Panels to edit the snakes live. The edited components are replicated to the clients like any other change.
+//!
+//! The tail points cannot be moved one by one, because that would break the invariants of the tail (segments are
+//! axis-aligned, and their total length is `TailLength::current_size`). Instead, the whole tail is moved with its head.
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+
+use shared::movement::{MAX_BOOST_SPEED, MIN_SPEED, MIN_TAIL_LENGTH};
+use shared::network::protocol::prelude::*;
+
+pub(crate) struct SnakeInspectorPlugin;
+
+impl Plugin for SnakeInspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, snake_panel);
+    }
+}
+
+fn snake_panel(
+    mut contexts: EguiContexts,
+    players: Query<&Player>,
+    mut snakes: Query<(Entity, &HasPlayer, &mut TailPoints, &mut TailLength, &mut Speed)>,
+) {
+    egui::Window::new("Snakes").show(contexts.ctx_mut(), |ui| {
+        for (entity, has_player, mut tail, mut length, mut speed) in snakes.iter_mut() {
+            let name = players.get(has_player.0).map_or("?".to_string(), |player| format!("{} ({})", player.name, player.id));
+            ui.collapsing(format!("{name} {entity:?}"), |ui| {
+                // only write the components that were edited, so that we don't replicate them every frame
+                let mut value = speed.0;
+                ui.horizontal(|ui| {
+                    ui.label("speed");
+                    ui.add(egui::Slider::new(&mut value, MIN_SPEED..=MAX_BOOST_SPEED));
+                });
+                if value != speed.0 {
+                    speed.0 = value;
+                }
+                let mut target_size = length.target_size;
+                ui.horizontal(|ui| {
+                    ui.label("target length");
+                    ui.add(egui::DragValue::new(&mut target_size).clamp_range(MIN_TAIL_LENGTH..=f32::MAX));
+                });
+                if target_size != length.target_size {
+                    length.target_size = target_size;
+                }
+                let head = tail.front().0;
+                let mut moved_head = head;
+                ui.horizontal(|ui| {
+                    ui.label("head");
+                    ui.add(egui::DragValue::new(&mut moved_head.x).prefix("x: "));
+                    ui.add(egui::DragValue::new(&mut moved_head.y).prefix("y: "));
+                });
+                if moved_head != head {
+                    let offset = moved_head - head;
+                    for (pos, _) in tail.0.iter_mut() {
+                        *pos += offset;
+                    }
+                }
+                ui.label("tail points (front to back)");
+                for (i, (pos, direction)) in tail.0.iter().enumerate() {
+                    ui.label(format!("{i}: ({:.1}, {:.1}) {direction:?}", pos.x, pos.y));
+                }
+            });
+        }
+    });
+}
@@ -1,22 +1,28 @@
 use bevy::prelude::*;
 use bevy::render::RenderPlugin;
-// use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
 pub(crate) mod snake;
 mod camera;
+mod inspector;
 
 
-pub(crate) struct DebugPlugin;
+pub(crate) struct DebugPlugin {
+    pub(crate) inspector: bool,
+}
 
 impl Plugin for DebugPlugin {
     fn build(&self, app: &mut App) {
         // plugins
         if app.is_plugin_added::<RenderPlugin>() {
-            // app.add_plugins(WorldInspectorPlugin::new());
+            if self.inspector {
+                app.add_plugins(WorldInspectorPlugin::new());
+                app.add_plugins(inspector::SnakeInspectorPlugin);
+            }
 
             // debug: render things on server
             app.add_plugins(snake::SnakeRenderPlugin);
             app.add_plugins(camera::CameraPlugin);
         }
     }
-}
\ No newline at end of file
+}
@@ -1,20 +1,27 @@
 use std::time::Duration;
+use bevy::ecs::entity::EntityHashMap;
 use bevy::prelude::*;
 use bevy::time::common_conditions::on_timer;
 use bevy_turborand::prelude::*;
 use bevy_xpbd_2d::prelude::{SpatialQuery, SpatialQueryFilter};
+use leafwing_input_manager::prelude::ActionState;
 use shared::collision::collider::ColliderSet;
 use shared::collision::layers::CollideLayer;
 use shared::map::{MapMarker, MapSize};
+use shared::movement::{boost_cost, Frozen, SimulationSet};
 use shared::network::bundle::food::FoodBundle;
 use shared::network::protocol::prelude::*;
-use shared::network::protocol::Replicate;
+use shared::network::protocol::{PlayerMovement, Replicate};
+
+use crate::network::validation::validate_turns;
 
 pub struct FoodPlugin;
 
 pub const FOOD_SPAWN_INTERVAL: Duration = Duration::from_secs(1);
 // pub const MAX_FOOD_COUNT: usize = 100;
 pub const TAIL_GROW_SIZE: f32 = 20.0;
+/// A food is dropped behind a boosting snake every time it burns this length
+pub const BOOST_FOOD_LENGTH: f32 = TAIL_GROW_SIZE;
 
 
 // spawn food
@@ -32,6 +39,31 @@ fn spawn_food(
     );
 }
 
+/// Drop food behind the snakes that burn their length by boosting.
+/// Runs before the movement, with the same inputs as `update_tails`, so that we know how much length they burn
+fn drop_boost_food(
+    mut commands: Commands,
+    mut burned: Local<EntityHashMap<f32>>,
+    snakes: Query<(Entity, &TailPoints, &TailLength, &ActionState<PlayerMovement>), Without<Frozen>>,
+) {
+    for (entity, tail, length, action) in snakes.iter() {
+        let cost = boost_cost(Some(action), length);
+        if cost == 0.0 {
+            continue;
+        }
+        let burned = burned.entry(entity).or_default();
+        *burned += cost;
+        if *burned >= BOOST_FOOD_LENGTH {
+            *burned -= BOOST_FOOD_LENGTH;
+            let pos = Position(tail.0.back().unwrap().0);
+            trace!(?entity, ?pos, "Dropping boost food");
+            commands.spawn((FoodBundle::new(pos), Replicate::default()));
+        }
+    }
+    // forget the snakes that died
+    burned.retain(|entity, _| snakes.contains(*entity));
+}
+
 // TODO: handle two players colliding with the same food at the same time
 // TODO: after the first collision is detected, remove the collider on the food!
 //  or set the food as 'dying'? maybe stop replicating it and then despawn?
@@ -64,7 +96,7 @@ fn food_collision(
     }
 }
 
-fn grow_tail(
+pub(crate) fn grow_tail(
     mut tails: Query<&mut TailLength>,
     mut events: EventReader<FoodCollision>,
 ) {
@@ -99,6 +131,8 @@ impl Plugin for FoodPlugin {
         // SYSTEMS
         // TODO: maybe run this before food collision?
         app.add_systems(Update, spawn_food.run_if(on_timer(FOOD_SPAWN_INTERVAL)),);
+        // the turn validation can drop inputs, so we read them after it like the movement does
+        app.add_systems(FixedUpdate, drop_boost_food.after(validate_turns).before(SimulationSet::Movement));
 
         app.add_systems(Update, (
             food_collision.in_set(ColliderSet::ComputeCollision),
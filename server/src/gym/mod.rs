@@ -0,0 +1,296 @@
+//! Training environment for snake agents, in the style of a gym environment.
+//!
+//! The environment runs the same simulation as the server (movement, friction, collisions and food) in a bevy `App`
+//! without any networking or rendering. Time is advanced manually by exactly one tick per step, so a plain rust loop
+//! can step the environment much faster than real time, and two runs with the same seed and actions are identical.
+//!
+//! ```ignore
+//! let mut env = SnakeEnv::new(EnvConfig::default());
+//! let mut observations = env.reset(0);
+//! loop {
+//!     let actions = observations.iter().map(|obs| policy(obs)).collect::<Vec<_>>();
+//!     let step = env.step(&actions);
+//!     if step.dones.iter().all(|done| *done) {
+//!         break;
+//!     }
+//!     observations = step.observations;
+//! }
+//! ```
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy_turborand::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use shared::collision::collider::ColliderSet;
+use shared::map::{MapPlugin, MAP_SIZE};
+use shared::movement::MovementPlugin;
+use shared::network::bundle::food::FoodBundle;
+use shared::network::bundle::snake::SnakeBundle;
+use shared::network::config::FIXED_TIMESTEP_HZ;
+use shared::network::protocol::PlayerMovement;
+use shared::network::protocol::prelude::*;
+use shared::network::protocol::prelude::Direction;
+
+use crate::collision::collider::ColliderPlugin;
+use crate::food::FoodPlugin;
+
+/// Reward for eating a food
+pub const FOOD_REWARD: f32 = 1.0;
+/// Reward for making another snake collide with our tail
+pub const KILL_REWARD: f32 = 5.0;
+/// Reward for dying
+pub const DEATH_REWARD: f32 = -10.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnvConfig {
+    /// Number of snakes controlled by the agents
+    pub num_snakes: usize,
+    /// Number of food items spawned on reset (more food keeps spawning over time, like on the server)
+    pub initial_food: usize,
+    /// Tail segments and food further than this distance from the head are not observed
+    pub view_radius: f32,
+    /// The episode ends after this many steps
+    pub max_steps: usize,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            num_snakes: 4,
+            initial_food: 50,
+            view_radius: 200.0,
+            max_steps: 10_000,
+        }
+    }
+}
+
+/// What an agent sees. All the positions are relative to the head of its snake
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Observation {
+    pub alive: bool,
+    /// absolute position of the head
+    pub head: Vec2,
+    pub direction: Option<Direction>,
+    pub speed: f32,
+    pub length: f32,
+    /// distance from the head to the edges of the map, in the order up, down, left, right
+    pub walls: [f32; 4],
+    /// tail segments (of any snake, including ours) that are within the view radius
+    pub tail_segments: Vec<(Vec2, Vec2)>,
+    /// food within the view radius
+    pub food: Vec<Vec2>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepResult {
+    pub observations: Vec<Observation>,
+    pub rewards: Vec<f32>,
+    /// true if the snake died, or if the episode reached `max_steps`
+    pub dones: Vec<bool>,
+}
+
+/// Collisions that happened during the last step
+#[derive(Resource, Debug, Default)]
+struct StepEvents {
+    food: Vec<Entity>,
+    // (killed, killer)
+    deaths: Vec<(Entity, Entity)>,
+}
+
+pub struct SnakeEnv {
+    config: EnvConfig,
+    app: App,
+    /// the snake of each agent, or None if it is dead
+    snakes: Vec<Option<Entity>>,
+    steps: usize,
+}
+
+impl SnakeEnv {
+    pub fn new(config: EnvConfig) -> Self {
+        let mut env = Self {
+            app: App::new(),
+            snakes: vec![],
+            steps: 0,
+            config,
+        };
+        env.reset(0);
+        env
+    }
+
+    /// Start a new episode. The episode only depends on the seed and on the actions
+    pub fn reset(&mut self, seed: u64) -> Vec<Observation> {
+        self.app = Self::build_app(seed);
+        self.steps = 0;
+
+        let mut rng = RngComponent::with_seed(seed);
+        let mut random_position = || Vec2::new(
+            rng.f32_normalized() * MAP_SIZE * 0.4,
+            rng.f32_normalized() * MAP_SIZE * 0.4,
+        );
+        let world = &mut self.app.world;
+        self.snakes = (0..self.config.num_snakes)
+            .map(|_| Some(world.spawn(SnakeBundle::new(random_position())).id()))
+            .collect();
+        for _ in 0..self.config.initial_food {
+            world.spawn(FoodBundle::new(Position(random_position())));
+        }
+        // run the startup systems and compute the colliders
+        self.app.update();
+        self.observations()
+    }
+
+    /// Apply one action per snake (None to keep going straight) and advance the simulation by one tick
+    pub fn step(&mut self, actions: &[Option<Direction>]) -> StepResult {
+        assert_eq!(actions.len(), self.snakes.len(), "there should be one action per snake");
+        for (snake, action) in self.snakes.iter().zip(actions) {
+            let Some(mut action_state) = snake.and_then(|snake| self.app.world.get_mut::<ActionState<PlayerMovement>>(snake)) else {
+                continue;
+            };
+            action_state.release_all();
+            if let Some(direction) = action {
+                action_state.press(&PlayerMovement::from(*direction));
+            }
+        }
+        self.app.update();
+        self.steps += 1;
+
+        let events = std::mem::take(&mut *self.app.world.resource_mut::<StepEvents>());
+        let mut rewards = vec![0.0; self.snakes.len()];
+        let mut dones = vec![self.steps >= self.config.max_steps; self.snakes.len()];
+        let agent = |entity: Entity| self.snakes.iter().position(|snake| *snake == Some(entity));
+        for snake in events.food {
+            if let Some(i) = agent(snake) {
+                rewards[i] += FOOD_REWARD;
+            }
+        }
+        let mut dead = vec![];
+        for (killed, killer) in events.deaths {
+            if let Some(i) = agent(killed) {
+                rewards[i] += DEATH_REWARD;
+                dones[i] = true;
+                dead.push(i);
+            }
+            if let Some(i) = agent(killer).filter(|_| killer != killed) {
+                rewards[i] += KILL_REWARD;
+            }
+        }
+        for i in dead {
+            if let Some(snake) = self.snakes[i].take() {
+                self.app.world.despawn(snake);
+            }
+        }
+        // dead snakes stay done until the next reset
+        for (done, snake) in dones.iter_mut().zip(self.snakes.iter()) {
+            *done |= snake.is_none();
+        }
+        StepResult {
+            observations: self.observations(),
+            rewards,
+            dones,
+        }
+    }
+
+    pub fn num_snakes(&self) -> usize {
+        self.snakes.len()
+    }
+
+    fn build_app(seed: u64) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        // one update is exactly one tick
+        let tick_duration = Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ);
+        app.insert_resource(Time::<Fixed>::from_duration(tick_duration));
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(tick_duration));
+        app.insert_resource(GlobalRng::with_seed(seed));
+        // simulation (we don't use the DeathPlugin because it sends messages to the clients)
+        app.add_plugins((MovementPlugin, MapPlugin));
+        app.add_plugins((shared::collision::CollisionPlugin, ColliderPlugin, FoodPlugin));
+        // record the collisions
+        app.init_resource::<StepEvents>();
+        app.add_systems(Update, record_events.after(ColliderSet::ComputeCollision));
+        app
+    }
+
+    fn observations(&mut self) -> Vec<Observation> {
+        let radius = self.config.view_radius;
+        let world = &mut self.app.world;
+        let tails: Vec<TailPoints> = world.query::<&TailPoints>().iter(world).cloned().collect();
+        let food: Vec<Vec2> = world.query_filtered::<&Position, With<FoodMarker>>().iter(world).map(|pos| pos.0).collect();
+        let mut snake_query = world.query::<(&TailPoints, &TailLength, &Speed)>();
+        let world = &*world;
+        self.snakes.iter().map(|snake| {
+            let Some((tail, length, speed)) = snake.and_then(|snake| snake_query.get(world, snake).ok()) else {
+                return Observation::default();
+            };
+            let (head, direction) = *tail.front();
+            let half_size = MAP_SIZE / 2.0;
+            Observation {
+                alive: true,
+                head,
+                direction: Some(direction),
+                speed: speed.0,
+                length: length.current_size,
+                walls: [half_size - head.y, head.y + half_size, head.x + half_size, half_size - head.x],
+                tail_segments: tails.iter()
+                    .flat_map(|tail| tail.pairs_front_to_back())
+                    .filter(|((from, _), (to, _))| distance_to_segment(head, *from, *to) < radius)
+                    .map(|((from, _), (to, _))| (*from - head, *to - head))
+                    .collect(),
+                food: food.iter()
+                    .filter(|pos| pos.distance(head) < radius)
+                    .map(|pos| *pos - head)
+                    .collect(),
+            }
+        }).collect()
+    }
+}
+
+fn record_events(
+    mut events: ResMut<StepEvents>,
+    mut food_collisions: EventReader<FoodCollision>,
+    mut snake_collisions: EventReader<SnakeCollision>,
+) {
+    events.food.extend(food_collisions.read().map(|collision| collision.snake));
+    events.deaths.extend(snake_collisions.read().map(|collision| (collision.killed, collision.killer)));
+}
+
+fn distance_to_segment(point: Vec2, from: Vec2, to: Vec2) -> f32 {
+    let segment = to - from;
+    let t = if segment.length_squared() == 0.0 {
+        0.0
+    } else {
+        ((point - from).dot(segment) / segment.length_squared()).clamp(0.0, 1.0)
+    };
+    point.distance(from + segment * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIRECTIONS: [Option<Direction>; 5] = [None, Some(Direction::Up), Some(Direction::Down), Some(Direction::Left), Some(Direction::Right)];
+
+    /// Run an episode with random actions, and return all the step results
+    fn run_episode(env: &mut SnakeEnv, seed: u64, steps: usize) -> Vec<StepResult> {
+        env.reset(seed);
+        let mut rng = RngComponent::with_seed(seed);
+        (0..steps).map(|_| {
+            let actions: Vec<_> = (0..env.num_snakes())
+                .map(|_| DIRECTIONS[rng.usize(0..DIRECTIONS.len())])
+                .collect();
+            env.step(&actions)
+        }).collect()
+    }
+
+    #[test]
+    fn test_deterministic_replay() {
+        let mut env = SnakeEnv::new(EnvConfig::default());
+        let first = run_episode(&mut env, 7, 500);
+        let second = run_episode(&mut env, 7, 500);
+        assert_eq!(first, second);
+        // the snakes moved
+        assert_ne!(first[0].observations, first[499].observations);
+    }
+}
@@ -1,15 +1,31 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
 use bevy::log::{Level, LogPlugin};
 use bevy::prelude::*;
 use clap::Parser;
+use lightyear::prelude::IoConfig;
 
-use shared::network::config::Transports;
+use shared::network::config::{LinkArgs, LinkSettings, Transports};
 use shared::SharedPlugin;
+use shared::utils::rand::{Seed, SEED};
+use crate::bot::{BotPlugin, Difficulty};
 use crate::food::FoodPlugin;
+use crate::replay::{ReplayPlugin, ReplaySettings};
+use crate::network::validation::ValidationPolicy;
+
+pub use crate::bot::BotSettings;
+pub use crate::collision::Invulnerable;
+pub use crate::network::connection_events::{GraceMode, PlayerCap, ReconnectPolicy};
 
 mod network;
 mod debug;
 pub(crate) mod collision;
 mod food;
+mod bot;
+pub mod gym;
+mod replay;
+mod appearance;
 
 pub const SERVER_PORT: u16 = 5000;
 
@@ -26,9 +42,71 @@ pub struct Cli {
 
     #[arg(short, long, value_enum, default_value_t = Transports::WebTransport)]
     transport: Transports,
+
+    /// Maximum number of turns a snake can make per window of `turn_window` ticks
+    #[arg(long, default_value_t = ValidationPolicy::default().max_turns_per_window)]
+    max_turns_per_window: usize,
+
+    #[arg(long, default_value_t = ValidationPolicy::default().turn_window)]
+    turn_window: u16,
+
+    /// Number of input violations after which a client is disconnected (0 to never disconnect)
+    #[arg(long, default_value_t = ValidationPolicy::default().max_violations)]
+    max_violations: u32,
+
+    /// How long (in seconds) a disconnected player is kept alive so that it can reconnect
+    #[arg(long, default_value_t = ReconnectPolicy::default().grace_period.as_secs_f32())]
+    reconnect_grace_period: f32,
+
+    /// What happens to the snake of a disconnected player during the grace period
+    #[arg(long, value_enum, default_value_t = GraceMode::Straight)]
+    grace_mode: GraceMode,
+
+    /// Maximum number of players with a snake; clients that connect when the server is full become spectators
+    #[arg(long, default_value_t = PlayerCap::default().0)]
+    max_players: usize,
+
+    /// Difficulty of the bots controlled by the server
+    #[arg(long, value_enum, default_value_t = Difficulty::Medium)]
+    bot_difficulty: Difficulty,
+
+    /// Bots are added until there are at least this many players (0 to disable bots)
+    #[arg(long, default_value_t = 0)]
+    min_population: usize,
+
+    /// If set, the bots try to cut in front of other snakes
+    #[arg(long, default_value = "false")]
+    bots_hunt: bool,
+
+    /// Directory where the matches are recorded (no recording if not set)
+    #[arg(long)]
+    replay_dir: Option<PathBuf>,
+
+    /// Number of ticks between two snapshots of the full state in the replay
+    #[arg(long, default_value_t = ReplaySettings::default().snapshot_interval)]
+    replay_snapshot_interval: u32,
+
+    #[command(flatten)]
+    link: LinkArgs,
+
+    /// Seed of the random number generator of the simulation
+    #[arg(long, default_value_t = SEED)]
+    seed: u64,
 }
 
 
+/// Settings of the game plugins of the server
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServerSettings {
+    /// show the debug panels
+    pub inspector: bool,
+    pub validation_policy: ValidationPolicy,
+    pub reconnect_policy: ReconnectPolicy,
+    pub player_cap: PlayerCap,
+    pub bot_settings: BotSettings,
+    pub replay_settings: ReplaySettings,
+}
+
 pub async fn app(cli: Cli) -> App {
     let mut app = App::new();
     if cli.headless {
@@ -46,11 +124,60 @@ pub async fn app(cli: Cli) -> App {
         }));
     }
 
+    // the seed needs to be set before the SharedPlugin is added
+    app.insert_resource(Seed(cli.seed));
+
+    // networking
+    let io = network::config::io_config(cli.port, cli.transport, cli.link.settings(LinkSettings::default())).await;
+    let validation_policy = ValidationPolicy {
+        max_turns_per_window: cli.max_turns_per_window,
+        turn_window: cli.turn_window,
+        max_violations: cli.max_violations,
+        ..default()
+    };
+    let reconnect_policy = ReconnectPolicy {
+        grace_period: Duration::from_secs_f32(cli.reconnect_grace_period),
+        mode: cli.grace_mode,
+    };
+    let settings = ServerSettings {
+        inspector: cli.inspector,
+        validation_policy,
+        reconnect_policy,
+        player_cap: PlayerCap(cli.max_players),
+        bot_settings: BotSettings {
+            difficulty: cli.bot_difficulty,
+            min_population: cli.min_population,
+            hunt: cli.bots_hunt,
+        },
+        replay_settings: ReplaySettings {
+            dir: cli.replay_dir,
+            snapshot_interval: cli.replay_snapshot_interval,
+        },
+    };
+    add_game_plugins(&mut app, io, settings);
+    app
+}
+
+/// Server app without rendering that communicates with its clients through `io`.
+/// Used to run the server in the same process as the clients (for example in the integration tests)
+pub fn local_app(io: IoConfig, settings: ServerSettings) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    add_game_plugins(&mut app, io, settings);
+    app
+}
+
+fn add_game_plugins(app: &mut App, io: IoConfig, settings: ServerSettings) {
     // networking
-    app.add_plugins(network::NetworkPluginGroup::new(cli.port, cli.transport).await.build());
+    app.add_plugins(network::NetworkPluginGroup::new(
+        io,
+        settings.validation_policy,
+        settings.reconnect_policy,
+        settings.player_cap,
+    ).build());
 
     // debug
-    app.add_plugins(debug::DebugPlugin);
+    app.add_plugins(debug::DebugPlugin { inspector: settings.inspector });
 
     // collisions
     app.add_plugins(collision::CollisionPlugin);
@@ -60,5 +187,13 @@ pub async fn app(cli: Cli) -> App {
 
     // food
     app.add_plugins(FoodPlugin);
-    app
-}
\ No newline at end of file
+
+    // bots
+    app.add_plugins(BotPlugin { settings: settings.bot_settings });
+
+    // replays
+    app.add_plugins(ReplayPlugin { settings: settings.replay_settings });
+
+    // cosmetics
+    app.add_plugins(appearance::AppearancePlugin);
+}
@@ -0,0 +1,63 @@
+//! Compute the checksum of the simulation every frame, and periodically send the checksum of each snake
+//! to the clients so that they can detect desyncs.
+//!
+//! The replicated state of a tick also contains the changes made in `Update` (for example the growth when a snake eats),
+//! so the checksum is computed right before the state gets replicated, after all the changes of the frame.
+use bevy::prelude::*;
+use lightyear::prelude::{MainSet, NetworkTarget, Tick, TickManager};
+
+use shared::movement::checksum::{combined_checksum, snake_checksum};
+use shared::network::protocol::prelude::*;
+
+/// Number of ticks between two checksums sent to the clients
+pub const CHECKSUM_INTERVAL: u16 = 64;
+
+/// Checksum of all the snakes at the last tick
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct SimulationChecksum {
+    pub tick: Tick,
+    pub value: u64,
+    /// tick of the last checksum sent to the clients
+    last_sent: Option<Tick>,
+}
+
+pub(crate) struct ChecksumPlugin;
+
+impl Plugin for ChecksumPlugin {
+    fn build(&self, app: &mut App) {
+        // resources
+        app.init_resource::<SimulationChecksum>();
+        // systems
+        app.add_systems(PostUpdate, compute_checksum.before(MainSet::Send));
+    }
+}
+
+fn compute_checksum(
+    tick_manager: Res<TickManager>,
+    mut checksum: ResMut<SimulationChecksum>,
+    mut connection_manager: ResMut<ServerConnectionManager>,
+    players: Query<&Player>,
+    snakes: Query<(Entity, &HasPlayer, &TailPoints, &TailLength, &Speed, &Acceleration)>,
+) {
+    let tick = tick_manager.tick();
+    let checksums: Vec<(Entity, u64, u64)> = snakes.iter()
+        .filter_map(|(entity, has_player, tail, length, speed, acceleration)| {
+            let player = players.get(has_player.0).ok()?;
+            Some((entity, player.id, snake_checksum(tail, length, speed, acceleration)))
+        })
+        .collect();
+    checksum.tick = tick;
+    checksum.value = combined_checksum(checksums.iter().map(|(_, client_id, checksum)| (*client_id, *checksum)).collect());
+    trace!(?tick, checksum = ?checksum.value, "Simulation checksum");
+
+    // several ticks can run in one frame, so we cannot wait for a tick that is a multiple of the interval
+    if !checksum.last_sent.is_some_and(|last_sent| tick - last_sent < CHECKSUM_INTERVAL as i16) {
+        checksum.last_sent = Some(tick);
+        let message = StateChecksum {
+            tick,
+            snakes: checksums.into_iter().map(|(entity, _, checksum)| (entity, checksum)).collect(),
+        };
+        let _ = connection_manager.send_message_to_target::<GameChannel, _>(message, NetworkTarget::All)
+            .map_err(|e| error!(?e, "Failed to send checksum"));
+    }
+}
@@ -1,54 +1,242 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 use bevy::utils::HashMap;
+use clap::ValueEnum;
+use leafwing_input_manager::prelude::ActionState;
 use lightyear::prelude::ClientId;
-use lightyear::server::events::{ConnectEvent, DisconnectEvent};
+use lightyear::server::events::{ConnectEvent, DisconnectEvent, MessageEvent};
 
+use shared::movement::Frozen;
 use shared::network::protocol::prelude::*;
+use shared::network::protocol::{DeadGameAction, PlayerMovement};
 
 use shared::network::bundle::player::PlayerBundle;
 use shared::network::bundle::snake::SnakeBundle;
+use crate::bot::Bot;
+use crate::collision::Invulnerable;
 
 #[derive(Resource, Debug, Default)]
 pub struct Global {
     // TODO: maybe lightyear can automatically create a Player entity, and maintain this map?
     /// map from client id to the player entity
     pub(crate) client_id_map: HashMap<ClientId, Entity>,
+    /// map from session token to the player entity
+    pub(crate) sessions: HashMap<u64, Entity>,
+}
+
+/// What happens to the snake of a player during the reconnect grace period
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GraceMode {
+    /// the snake keeps going straight, and can die
+    Straight,
+    /// the snake stops moving and cannot die
+    Frozen,
+}
+
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct ReconnectPolicy {
+    /// how long a disconnected player is kept alive (0 to despawn the player immediately)
+    pub grace_period: Duration,
+    pub mode: GraceMode,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            grace_period: Duration::from_secs(10),
+            mode: GraceMode::Straight,
+        }
+    }
+}
+
+/// Maximum number of players that have a snake (bots and spectators are not counted).
+/// Clients that connect when the server is full join as spectators
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct PlayerCap(pub usize);
+
+impl Default for PlayerCap {
+    fn default() -> Self {
+        Self(16)
+    }
 }
 
+/// Marker for a player whose client connected but did not send `Join` yet: it has no snake, and is not counted
+/// in the `PlayerCap`
+#[derive(Component, Debug)]
+pub(crate) struct AwaitingJoin;
+
+/// Marker for a player whose client disconnected, and who can still be resumed until the timer finishes
+#[derive(Component, Debug)]
+pub(crate) struct Disconnected {
+    pub(crate) timer: Timer,
+}
+
+/// Create the player of the new clients. Their snake is only spawned when they send `Join`, so that spectators
+/// never get a snake
 pub(crate) fn handle_connections(
     mut global: ResMut<Global>,
     mut connections: EventReader<ConnectEvent>,
+    mut connection_manager: ResMut<ServerConnectionManager>,
     mut commands: Commands,
 ) {
     for connection in connections.read() {
         let client_id = connection.context();
-        let head_entity = SnakeBundle::spawn(&mut commands, *client_id);
-        let player_entity = PlayerBundle::new(Player {
+        // distinct names, so that players can tell who killed them
+        let player = Player {
             id: *client_id,
-            name: "Player".to_string(),
-            snake: Some(head_entity),
-        }).spawn(&mut commands, *client_id);
-        commands.entity(head_entity).insert(HasPlayer(player_entity));
+            name: format!("Player {client_id}"),
+        };
+        let player_entity = PlayerBundle::new(player).spawn(&mut commands, *client_id);
+        commands.entity(player_entity).insert(AwaitingJoin);
         global.client_id_map.insert(*client_id, player_entity);
+
+        // give the client a token that it can use to resume its session after a disconnection
+        let token = session_token();
+        global.sessions.insert(token, player_entity);
+        let _ = connection_manager.send_message::<GameChannel, _>(*client_id, SessionToken { token })
+            .map_err(|e| error!(?e, "Failed to send session token"));
     }
 }
 
+/// Session tokens must not be predictable (otherwise anyone could take the player of a disconnected client),
+/// so they come from the OS and not from the `GlobalRng`, which is seeded deterministically
+fn session_token() -> u64 {
+    let mut bytes = [0; 8];
+    getrandom::getrandom(&mut bytes).expect("failed to generate a session token");
+    u64::from_le_bytes(bytes)
+}
+
 pub(crate) fn handle_disconnections(
+    policy: Res<ReconnectPolicy>,
     mut global: ResMut<Global>,
     mut disconnects: EventReader<DisconnectEvent>,
-    player_query: Query<&Player>,
+    player_query: Query<Option<&Children>, With<Player>>,
+    mut snake_query: Query<&mut ActionState<PlayerMovement>>,
     mut commands: Commands,
 ) {
     for disconnect in disconnects.read() {
         let client_id = disconnect.context();
-        if let Some(player_entity) = global.client_id_map.remove(client_id) {
-            if let Ok(player) = player_query.get(player_entity) {
-                if let Some(snake_entity) = player.snake {
-                    // TODO: to delete the tail, we need to maintain a child/parent relationship!
-                    commands.entity(snake_entity).despawn_recursive();
-                }
+        let Some(player_entity) = global.client_id_map.remove(client_id) else {
+            continue;
+        };
+        let Ok(children) = player_query.get(player_entity) else {
+            continue;
+        };
+        if policy.grace_period.is_zero() {
+            despawn_player(&mut commands, &mut global, player_entity);
+            continue;
+        }
+        info!(?client_id, "Client disconnected, keeping its player during the grace period");
+        commands.entity(player_entity).insert(Disconnected {
+            timer: Timer::new(policy.grace_period, TimerMode::Once),
+        });
+        for &snake_entity in children.into_iter().flatten() {
+            // the last inputs of the client should not keep the snake turning
+            if let Ok(mut action) = snake_query.get_mut(snake_entity) {
+                *action = ActionState::default();
+            }
+            if policy.mode == GraceMode::Frozen {
+                commands.entity(snake_entity).insert((Frozen, Invulnerable));
             }
-            commands.entity(player_entity).despawn_recursive();
         }
     }
-}
\ No newline at end of file
+}
+
+/// Despawn the players whose grace period has expired
+pub(crate) fn expire_disconnected_players(
+    time: Res<Time>,
+    mut global: ResMut<Global>,
+    mut players: Query<(Entity, &Player, &mut Disconnected)>,
+    mut commands: Commands,
+) {
+    for (player_entity, player, mut disconnected) in players.iter_mut() {
+        if disconnected.timer.tick(time.delta()).finished() {
+            info!(client_id = ?player.id, "Reconnect grace period expired");
+            despawn_player(&mut commands, &mut global, player_entity);
+        }
+    }
+}
+
+/// A reconnecting client sent its session token: give it back its player and snake
+pub(crate) fn handle_resume_session(
+    mut global: ResMut<Global>,
+    mut messages: EventReader<MessageEvent<ResumeSession>>,
+    mut connection_manager: ResMut<ServerConnectionManager>,
+    mut players: Query<(&mut Player, Option<&Children>)>,
+    disconnected: Query<(), With<Disconnected>>,
+    mut commands: Commands,
+) {
+    for message in messages.read() {
+        let client_id = *message.context();
+        let token = message.message().token;
+        let Some(&player_entity) = global.sessions.get(&token) else {
+            warn!(?client_id, "Received unknown session token");
+            continue;
+        };
+        if disconnected.get(player_entity).is_err() {
+            warn!(?client_id, "Session is still in use by another client");
+            continue;
+        }
+        // despawn the player that was created when the client connected
+        if let Some(new_player_entity) = global.client_id_map.insert(client_id, player_entity) {
+            despawn_player(&mut commands, &mut global, new_player_entity);
+        }
+        let Ok((mut player, children)) = players.get_mut(player_entity) else {
+            continue;
+        };
+        info!(?client_id, "Resuming session");
+        // confirm to the client that it is now using the resumed session
+        let _ = connection_manager.send_message::<GameChannel, _>(client_id, SessionToken { token })
+            .map_err(|e| error!(?e, "Failed to send session token"));
+        player.id = client_id;
+        commands.entity(player_entity)
+            .remove::<Disconnected>()
+            .insert(PlayerBundle::replicate(client_id));
+        for &snake_entity in children.into_iter().flatten() {
+            commands.entity(snake_entity)
+                .remove::<(Frozen, Invulnerable)>()
+                .insert(SnakeBundle::replicate(client_id));
+        }
+    }
+}
+
+/// The client joined: spawn its snake, or make it a spectator if it wants to watch the match or if the server is full.
+/// Clients that resumed their session already have a player, so their `Join` is ignored
+pub(crate) fn handle_join(
+    cap: Res<PlayerCap>,
+    global: Res<Global>,
+    mut messages: EventReader<MessageEvent<Join>>,
+    awaiting: Query<&Player, With<AwaitingJoin>>,
+    players: Query<(), (With<Player>, Without<Spectator>, Without<Bot>, Without<AwaitingJoin>)>,
+    mut commands: Commands,
+) {
+    let mut num_players = players.iter().count();
+    for message in messages.read() {
+        let client_id = *message.context();
+        let Some(&player_entity) = global.client_id_map.get(&client_id) else {
+            continue;
+        };
+        if awaiting.get(player_entity).is_err() {
+            continue;
+        }
+        commands.entity(player_entity).remove::<AwaitingJoin>();
+        let full = num_players >= cap.0;
+        if message.message().spectate || full {
+            info!(?client_id, full, "Client joins as a spectator");
+            commands.entity(player_entity)
+                .remove::<ActionState<DeadGameAction>>()
+                .insert(Spectator);
+            continue;
+        }
+        num_players += 1;
+        let head_entity = SnakeBundle::spawn(&mut commands, client_id);
+        commands.entity(head_entity).insert(HasPlayer(player_entity));
+    }
+}
+
+/// Despawn the player; its snake is a child of the player so it gets despawned as well
+pub(crate) fn despawn_player(commands: &mut Commands, global: &mut Global, player_entity: Entity) {
+    commands.entity(player_entity).despawn_recursive();
+    global.sessions.retain(|_, entity| *entity != player_entity);
+}
@@ -1,5 +1,5 @@
 use bevy::app::{App, Plugin};
-use bevy::prelude::{Commands, Entity, Query, Update};
+use bevy::prelude::{Children, Commands, Entity, Query, Update, Without};
 use leafwing_input_manager::prelude::ActionState;
 use lightyear::server::input_leafwing::LeafwingInputPlugin;
 use tracing::info;
@@ -8,6 +8,8 @@ use shared::network::protocol::{DeadGameAction, GameProtocol, PlayerMovement};
 use shared::network::protocol::prelude::{HasPlayer, Player};
 use shared::network::bundle::snake::SnakeBundle;
 
+use crate::network::connection_events::AwaitingJoin;
+
 pub struct NetworkInputsPlugin;
 
 
@@ -24,16 +26,18 @@ impl Plugin for NetworkInputsPlugin {
 
 fn handle_game_action(
     mut commands: Commands,
-    mut players: Query<(Entity, &mut Player, &ActionState<DeadGameAction>)>
+    // players that did not join yet get their snake when they join
+    players: Query<(Entity, &Player, &ActionState<DeadGameAction>, Option<&Children>), Without<AwaitingJoin>>
 ) {
-    for (player_entity, mut player, action_state) in players.iter_mut() {
-        if action_state.just_pressed(&DeadGameAction::Spawn) {
+    for (player_entity, player, action_state, children) in players.iter() {
+        // the snake is a child of the player: players that still have a snake cannot respawn
+        let has_snake = children.is_some_and(|children| !children.is_empty());
+        if !has_snake && action_state.just_pressed(&DeadGameAction::Spawn) {
             info!(?player, "Respawning player");
             let client_id = player.id;
             // respawn the snake
             let head_entity = SnakeBundle::spawn(&mut commands, client_id);
             commands.entity(head_entity).insert(HasPlayer(player_entity));
-            player.snake = Some(head_entity);
         }
     }
 }
\ No newline at end of file
@@ -2,48 +2,84 @@ use bevy::app::PluginGroupBuilder;
 use bevy::prelude::*;
 use lightyear::prelude::server::*;
 
-use shared::network::config::Transports;
+use lightyear::prelude::IoConfig;
 use shared::network::protocol::GameProtocol;
 
+use crate::network::checksum::ChecksumPlugin;
+use crate::network::connection_events::{PlayerCap, ReconnectPolicy};
 use crate::network::inputs::NetworkInputsPlugin;
+use crate::network::validation::{InputValidationPlugin, ValidationPolicy};
 
-mod config;
-mod connection_events;
+pub(crate) mod checksum;
+pub(crate) mod config;
+pub(crate) mod connection_events;
 mod inputs;
+pub(crate) mod validation;
 
 pub(crate) struct NetworkPluginGroup {
     pub(crate) lightyear: ServerPlugin<GameProtocol>,
+    pub(crate) validation_policy: ValidationPolicy,
+    pub(crate) reconnect_policy: ReconnectPolicy,
+    pub(crate) player_cap: PlayerCap,
 }
 
 impl PluginGroup for NetworkPluginGroup {
     fn build(self) -> PluginGroupBuilder {
         PluginGroupBuilder::start::<Self>()
             .add(self.lightyear)
-            .add(NetworkPlugin)
+            .add(NetworkPlugin {
+                validation_policy: self.validation_policy,
+                reconnect_policy: self.reconnect_policy,
+                player_cap: self.player_cap,
+            })
     }
 }
 
 impl NetworkPluginGroup {
-    pub async fn new(port: u16, transport: Transports) -> Self {
-        let lightyear = config::build_plugin(port, transport).await;
+    pub fn new(
+        io: IoConfig,
+        validation_policy: ValidationPolicy,
+        reconnect_policy: ReconnectPolicy,
+        player_cap: PlayerCap,
+    ) -> Self {
+        let lightyear = config::build_plugin(io);
         Self {
             lightyear,
+            validation_policy,
+            reconnect_policy,
+            player_cap,
         }
     }
 }
 
-pub struct NetworkPlugin;
+pub struct NetworkPlugin {
+    pub(crate) validation_policy: ValidationPolicy,
+    pub(crate) reconnect_policy: ReconnectPolicy,
+    pub(crate) player_cap: PlayerCap,
+}
 
 impl Plugin for NetworkPlugin {
     fn build(&self, app: &mut App) {
         // plugins
         app.add_plugins(NetworkInputsPlugin);
+        app.add_plugins(ChecksumPlugin);
+        app.add_plugins(InputValidationPlugin {
+            policy: self.validation_policy.clone(),
+        });
 
         // resources
         app.init_resource::<connection_events::Global>();
+        app.insert_resource(self.reconnect_policy.clone());
+        app.insert_resource(self.player_cap.clone());
 
         // systems
-        app.add_systems(Update, (connection_events::handle_connections, connection_events::handle_disconnections));
+        app.add_systems(Update, (
+            connection_events::handle_connections,
+            connection_events::handle_disconnections,
+            connection_events::handle_resume_session.after(connection_events::handle_connections),
+            connection_events::expire_disconnected_players,
+            connection_events::handle_join.after(connection_events::handle_resume_session),
+        ));
 
     }
 }
@@ -0,0 +1,394 @@
+//! Server-side validation of the inputs received from the clients.
+//!
+//! The server applies whatever `ActionState<PlayerMovement>` it receives, so we check the inputs before
+//! the simulation runs:
+//! - turns are rate-limited over a window of ticks
+//! - input histories that a human could not produce (turning every tick for a long time) are flagged
+//! - clients that send too many inputs, or inputs for ticks too far ahead of the server, are flagged
+//!
+//! Every violation is logged. Clients that accumulate too many violations are kicked: they are disconnected and
+//! cannot resume their session.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+use bevy::utils::HashMap;
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::inputs::leafwing::input_buffer::InputBuffer;
+use lightyear::prelude::{ClientId, Tick, TickManager};
+use lightyear::prelude::server::ServerConnections;
+
+use shared::movement::{SimulationSet, turn_direction};
+use shared::network::protocol::{DeadGameAction, PlayerMovement, ServerConnectionManager};
+use shared::network::protocol::prelude::*;
+
+use crate::bot::Bot;
+use crate::network::connection_events::{despawn_player, Global};
+
+/// Interval at which we check the number of inputs sent by each client
+pub const INPUT_COUNT_INTERVAL: Duration = Duration::from_secs(1);
+/// Interval at which we forget one violation for each client
+pub const VIOLATION_DECAY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Policy used to validate the inputs of the clients
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ValidationPolicy {
+    /// maximum number of turns that a snake can make during `turn_window` ticks
+    pub max_turns_per_window: usize,
+    /// number of ticks over which the turns are counted
+    pub turn_window: u16,
+    /// number of consecutive ticks with a turn after which the input history is considered impossible
+    pub max_consecutive_turns: u16,
+    /// maximum number of key presses that a client can send during `INPUT_COUNT_INTERVAL`
+    pub max_inputs_per_interval: u32,
+    /// maximum number of ticks that the inputs of a client can be ahead of the server
+    pub max_input_lead: u16,
+    /// number of violations after which the client is disconnected (0 to never disconnect)
+    pub max_violations: u32,
+}
+
+impl Default for ValidationPolicy {
+    fn default() -> Self {
+        Self {
+            max_turns_per_window: 8,
+            turn_window: 32,
+            max_consecutive_turns: 6,
+            max_inputs_per_interval: 60,
+            max_input_lead: 64,
+            max_violations: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// the snake tried to turn more than `max_turns_per_window` times
+    TurnRateExceeded,
+    /// the snake turned on more than `max_consecutive_turns` consecutive ticks
+    ImpossibleHistory,
+    /// the client sent more than `max_inputs_per_interval` inputs
+    InputFlood,
+    /// the client sent inputs for ticks that are too far ahead of the server
+    OutOfWindowTick,
+}
+
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct InputViolation {
+    pub client_id: ClientId,
+    pub kind: ViolationKind,
+}
+
+/// Recent turns of a snake, used to rate-limit the turns
+#[derive(Component, Debug, Default)]
+pub struct TurnHistory {
+    turns: VecDeque<Tick>,
+    last_turn: Option<Tick>,
+    consecutive_turns: u16,
+}
+
+impl TurnHistory {
+    /// Forget the turns that are outside the window
+    fn forget_old_turns(&mut self, tick: Tick, policy: &ValidationPolicy) {
+        while self.turns.front().is_some_and(|t| tick - *t >= policy.turn_window as i16) {
+            self.turns.pop_front();
+        }
+    }
+
+    /// Record a turn made at `tick`, and return the violation if the turn breaks the policy.
+    /// Turns that exceed the rate limit are not recorded, because they are dropped
+    fn record_turn(&mut self, tick: Tick, policy: &ValidationPolicy) -> Option<ViolationKind> {
+        self.forget_old_turns(tick, policy);
+        if self.turns.len() >= policy.max_turns_per_window {
+            return Some(ViolationKind::TurnRateExceeded);
+        }
+        self.consecutive_turns = match self.last_turn {
+            Some(last_turn) if tick - last_turn == 1 => self.consecutive_turns + 1,
+            _ => 1,
+        };
+        self.last_turn = Some(tick);
+        self.turns.push_back(tick);
+        (self.consecutive_turns > policy.max_consecutive_turns).then_some(ViolationKind::ImpossibleHistory)
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct Offenders {
+    /// number of inputs sent by each client during the current interval
+    inputs: HashMap<ClientId, u32>,
+    /// number of violations of each client
+    violations: HashMap<ClientId, u32>,
+    /// clients that were sent a `Kicked` message, and that will be disconnected at the next frame
+    kicked: Vec<ClientId>,
+}
+
+impl Offenders {
+    /// Add a violation to the client, and return true if the client has too many violations and must be disconnected
+    fn add_violation(&mut self, client_id: ClientId, policy: &ValidationPolicy) -> bool {
+        let violations = self.violations.entry(client_id).or_default();
+        *violations += 1;
+        if policy.max_violations > 0 && *violations >= policy.max_violations {
+            self.violations.remove(&client_id);
+            return true;
+        }
+        false
+    }
+
+    /// Forget one violation of each client
+    fn forgive(&mut self) {
+        self.violations.retain(|_, violations| {
+            *violations -= 1;
+            *violations > 0
+        });
+    }
+}
+
+pub struct InputValidationPlugin {
+    pub policy: ValidationPolicy,
+}
+
+impl Plugin for InputValidationPlugin {
+    fn build(&self, app: &mut App) {
+        // events
+        app.add_event::<InputViolation>();
+        // resources
+        app.insert_resource(self.policy.clone());
+        app.init_resource::<Offenders>();
+        // systems
+        // the inputs need to be validated before they are used to move the snakes
+        // inputs are applied once per tick, so they are also counted once per tick (independently of the frame rate)
+        app.add_systems(FixedUpdate, (add_turn_history, validate_turns, count_inputs)
+            .chain()
+            .before(SimulationSet::Movement));
+        app.add_systems(Update, (
+            (validate_input_window, check_input_flood.run_if(on_timer(INPUT_COUNT_INTERVAL))).chain(),
+            (disconnect_kicked_clients, handle_violations).chain().after(check_input_flood).after(validate_input_window),
+            forgive_violations.run_if(on_timer(VIOLATION_DECAY_INTERVAL)),
+        ));
+    }
+}
+
+fn add_turn_history(
+    mut commands: Commands,
+    snakes: Query<Entity, (With<TailPoints>, Without<TurnHistory>)>,
+) {
+    for entity in snakes.iter() {
+        commands.entity(entity).insert(TurnHistory::default());
+    }
+}
+
+/// Rate-limit the turns of each snake, and flag snakes that turn on too many consecutive ticks
+pub(crate) fn validate_turns(
+    policy: Res<ValidationPolicy>,
+    tick_manager: Res<TickManager>,
+    players: Query<&Player, Without<Bot>>,
+    mut snakes: Query<(&HasPlayer, &TailPoints, &mut TurnHistory, &mut ActionState<PlayerMovement>)>,
+    mut writer: EventWriter<InputViolation>,
+) {
+    let tick = tick_manager.tick();
+    for (has_player, tail, mut history, mut action) in snakes.iter_mut() {
+        let Ok(player) = players.get(has_player.0) else {
+            continue;
+        };
+        history.forget_old_turns(tick, &policy);
+        // only inputs that would make the snake turn are rate-limited
+        if turn_direction(&action, tail.front().1).is_none() {
+            continue;
+        }
+        let Some(kind) = history.record_turn(tick, &policy) else {
+            continue;
+        };
+        if kind == ViolationKind::TurnRateExceeded {
+            // drop the input, the snake keeps going straight
+            drop_turn(&mut action);
+        }
+        writer.send(InputViolation {
+            client_id: player.id,
+            kind,
+        });
+    }
+}
+
+/// Consume the direction inputs only: consumed actions stay consumed until they are released, so consuming
+/// `Boost`/`Brake` would cancel them on the server while the client keeps predicting them
+fn drop_turn(action: &mut ActionState<PlayerMovement>) {
+    for direction in [PlayerMovement::Up, PlayerMovement::Down, PlayerMovement::Left, PlayerMovement::Right] {
+        action.consume(&direction);
+    }
+}
+
+/// Count the number of key presses sent by each client
+fn count_inputs(
+    mut offenders: ResMut<Offenders>,
+    players: Query<(&Player, &ActionState<DeadGameAction>), Without<Bot>>,
+    snakes: Query<(&HasPlayer, &ActionState<PlayerMovement>)>,
+) {
+    for (player, action) in players.iter() {
+        let count = action.get_just_pressed().len() as u32;
+        *offenders.inputs.entry(player.id).or_default() += count;
+    }
+    for (has_player, action) in snakes.iter() {
+        let Ok((player, _)) = players.get(has_player.0) else {
+            continue;
+        };
+        let count = action.get_just_pressed().len() as u32;
+        *offenders.inputs.entry(player.id).or_default() += count;
+    }
+}
+
+/// Flag the clients that sent too many inputs during the last interval
+fn check_input_flood(
+    policy: Res<ValidationPolicy>,
+    mut offenders: ResMut<Offenders>,
+    mut writer: EventWriter<InputViolation>,
+) {
+    for (client_id, count) in offenders.inputs.drain() {
+        if count > policy.max_inputs_per_interval {
+            writer.send(InputViolation {
+                client_id,
+                kind: ViolationKind::InputFlood,
+            });
+        }
+    }
+}
+
+/// Flag the clients whose buffered inputs are too far ahead of the server tick
+fn validate_input_window(
+    policy: Res<ValidationPolicy>,
+    tick_manager: Res<TickManager>,
+    players: Query<&Player, Without<Bot>>,
+    snakes: Query<(&HasPlayer, &InputBuffer<PlayerMovement>)>,
+    mut writer: EventWriter<InputViolation>,
+) {
+    let tick = tick_manager.tick();
+    for (has_player, buffer) in snakes.iter() {
+        let Ok(player) = players.get(has_player.0) else {
+            continue;
+        };
+        if buffer.end_tick().is_some_and(|end_tick| end_tick - tick > policy.max_input_lead as i16) {
+            writer.send(InputViolation {
+                client_id: player.id,
+                kind: ViolationKind::OutOfWindowTick,
+            });
+        }
+    }
+}
+
+/// Log the violations, and kick the clients that have too many violations.
+/// Their player is despawned and their session dropped, so that they cannot resume it by reconnecting
+fn handle_violations(
+    policy: Res<ValidationPolicy>,
+    mut global: ResMut<Global>,
+    mut offenders: ResMut<Offenders>,
+    mut connection_manager: ResMut<ServerConnectionManager>,
+    mut events: EventReader<InputViolation>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        warn!(client_id = ?event.client_id, kind = ?event.kind, "Input violation");
+        if offenders.add_violation(event.client_id, &policy) {
+            info!(client_id = ?event.client_id, "Kicking client because of repeated input violations");
+            if let Some(player_entity) = global.client_id_map.remove(&event.client_id) {
+                despawn_player(&mut commands, &mut global, player_entity);
+            }
+            let _ = connection_manager.send_message::<GameChannel, _>(event.client_id, Kicked {
+                reason: "too many input violations".to_string(),
+            }).map_err(|e| error!(?e, "Failed to send kick message"));
+            offenders.kicked.push(event.client_id);
+        }
+    }
+}
+
+/// Disconnect the kicked clients one frame after the `Kicked` message, so that the message gets sent first
+fn disconnect_kicked_clients(
+    mut offenders: ResMut<Offenders>,
+    mut connections: ResMut<ServerConnections>,
+) {
+    for client_id in offenders.kicked.drain(..) {
+        let _ = connections.disconnect(client_id).map_err(|e| error!(?e, "Failed to disconnect client"));
+    }
+}
+
+/// Violations are forgiven over time, so that only repeat offenders get disconnected
+fn forgive_violations(mut offenders: ResMut<Offenders>) {
+    offenders.forgive();
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::network::protocol::prelude::Direction;
+
+    use super::*;
+
+    fn policy() -> ValidationPolicy {
+        ValidationPolicy {
+            max_turns_per_window: 3,
+            turn_window: 10,
+            max_consecutive_turns: 2,
+            max_violations: 3,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn test_turn_rate_limit() {
+        let policy = policy();
+        let mut history = TurnHistory::default();
+        assert_eq!(history.record_turn(Tick(0), &policy), None);
+        assert_eq!(history.record_turn(Tick(2), &policy), None);
+        assert_eq!(history.record_turn(Tick(4), &policy), None);
+        // the 4th turn in the window is dropped
+        assert_eq!(history.record_turn(Tick(6), &policy), Some(ViolationKind::TurnRateExceeded));
+        assert_eq!(history.turns.len(), 3);
+        // the first turn is now outside the window
+        assert_eq!(history.record_turn(Tick(10), &policy), None);
+    }
+
+    #[test]
+    fn test_consecutive_turn_limit() {
+        let policy = policy();
+        let mut history = TurnHistory::default();
+        assert_eq!(history.record_turn(Tick(0), &policy), None);
+        assert_eq!(history.record_turn(Tick(1), &policy), None);
+        assert_eq!(history.record_turn(Tick(2), &policy), Some(ViolationKind::ImpossibleHistory));
+
+        // a pause resets the consecutive turns
+        let mut history = TurnHistory::default();
+        assert_eq!(history.record_turn(Tick(0), &policy), None);
+        assert_eq!(history.record_turn(Tick(1), &policy), None);
+        assert_eq!(history.record_turn(Tick(3), &policy), None);
+        assert_eq!(history.consecutive_turns, 1);
+    }
+
+    #[test]
+    fn test_dropped_turn_keeps_boost() {
+        let mut action = ActionState::<PlayerMovement>::default();
+        action.press(&PlayerMovement::Left);
+        action.press(&PlayerMovement::Boost);
+        action.press(&PlayerMovement::Brake);
+        drop_turn(&mut action);
+        assert_eq!(turn_direction(&action, Direction::Up), None);
+        assert!(action.pressed(&PlayerMovement::Boost));
+        assert!(action.pressed(&PlayerMovement::Brake));
+    }
+
+    #[test]
+    fn test_forgive_violations() {
+        let policy = policy();
+        let mut offenders = Offenders::default();
+        let client_id: ClientId = 1;
+        assert!(!offenders.add_violation(client_id, &policy));
+        assert!(!offenders.add_violation(client_id, &policy));
+        // one violation is forgiven, so the client is not disconnected at the next violation
+        offenders.forgive();
+        assert!(!offenders.add_violation(client_id, &policy));
+        assert!(offenders.add_violation(client_id, &policy));
+        // the violations are reset after the disconnection
+        assert!(!offenders.violations.contains_key(&client_id));
+
+        // clients without violations are forgotten
+        offenders.add_violation(client_id, &policy);
+        offenders.forgive();
+        assert!(offenders.violations.is_empty());
+    }
+}
@@ -0,0 +1,247 @@
+//! Record the match to a replay file (see `shared::replay` for the format).
+//!
+//! We record the connections, the inputs that the server applied at each tick (after validation), the spawns and
+//! deaths, and a snapshot of the full state every `snapshot_interval` ticks.
+//!
+//! Events are attributed to the next tick that gets simulated: `FixedUpdate` runs before `Update` in a frame, so
+//! the events gathered in `Update` (connections, deaths) happened after the last tick of the frame. The viewer
+//! applies the events of a tick before simulating it.
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::ecs::query::QueryItem;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::prelude::ClientId;
+use lightyear::server::events::{ConnectEvent, DisconnectEvent};
+
+use shared::collision::collider::ColliderSet;
+use shared::movement::SimulationSet;
+use shared::network::config::FIXED_TIMESTEP_HZ;
+use shared::network::protocol::PlayerMovement;
+use shared::network::protocol::prelude::*;
+use shared::replay::{
+    MovementInput, PlayerState, REPLAY_EXTENSION, ReplayEvent, ReplayFrame, ReplayHeader, ReplayWriter, SnakeState,
+    Snapshot,
+};
+use shared::utils::rand::Seed;
+
+use crate::food::grow_tail;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplaySettings {
+    /// directory where the replays are written (None to disable recording)
+    pub dir: Option<PathBuf>,
+    /// number of ticks between two snapshots
+    pub snapshot_interval: u32,
+}
+
+impl Default for ReplaySettings {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            snapshot_interval: 128,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct ReplayRecorder {
+    writer: ReplayWriter,
+    snapshot_interval: u32,
+    tick: u32,
+    /// events that happened since the last frame was written; they are written with the next tick
+    events: Vec<ReplayEvent>,
+    /// last recorded input of each client, so that we only record changes
+    inputs: HashMap<ClientId, MovementInput>,
+}
+
+impl ReplayRecorder {
+    fn write(&mut self, frame: ReplayFrame) {
+        let _ = self.writer.write_frame(&frame).map_err(|e| error!(?e, "Failed to write replay frame"));
+    }
+}
+
+pub struct ReplayPlugin {
+    pub settings: ReplaySettings,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(dir) = self.settings.dir.clone() else {
+            return;
+        };
+        let snapshot_interval = self.settings.snapshot_interval.max(1);
+        // systems
+        app.add_systems(Startup, move |mut commands: Commands, seed: Res<Seed>| {
+            if let Some(recorder) = start_recording(&dir, snapshot_interval, seed.0) {
+                commands.insert_resource(recorder);
+            }
+        });
+        app.add_systems(Update, (
+            record_connections,
+            record_deaths.after(ColliderSet::ComputeCollision),
+            record_growth.after(grow_tail),
+        ).run_if(resource_exists::<ReplayRecorder>));
+        app.add_systems(FixedUpdate, (
+            record_spawns.before(SimulationSet::Movement),
+            record_tick.after(SimulationSet::Movement),
+        ).run_if(resource_exists::<ReplayRecorder>));
+    }
+}
+
+fn start_recording(dir: &PathBuf, snapshot_interval: u32, seed: u64) -> Option<ReplayRecorder> {
+    let start_time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let header = ReplayHeader {
+        seed,
+        tick_rate: FIXED_TIMESTEP_HZ,
+        start_time,
+    };
+    let path = dir.join(format!("match-{start_time}.{REPLAY_EXTENSION}"));
+    let writer = std::fs::create_dir_all(dir)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| ReplayWriter::create(&path, &header))
+        .map_err(|e| error!(?e, "Failed to start recording the replay"))
+        .ok()?;
+    info!(?path, "Recording replay");
+    Some(ReplayRecorder {
+        writer,
+        snapshot_interval,
+        tick: 0,
+        events: vec![],
+        inputs: HashMap::default(),
+    })
+}
+
+fn record_connections(
+    mut recorder: ResMut<ReplayRecorder>,
+    mut connections: EventReader<ConnectEvent>,
+    mut disconnections: EventReader<DisconnectEvent>,
+) {
+    for connection in connections.read() {
+        recorder.events.push(ReplayEvent::Connect(*connection.context()));
+    }
+    for disconnection in disconnections.read() {
+        recorder.events.push(ReplayEvent::Disconnect(*disconnection.context()));
+    }
+}
+
+type SnakeQueryData = (
+    &'static HasPlayer,
+    &'static TailPoints,
+    &'static TailLength,
+    &'static Speed,
+    &'static Acceleration,
+    &'static ActionState<PlayerMovement>,
+);
+
+fn snake_state(
+    client_id: ClientId,
+    (_, tail, length, speed, acceleration, action): QueryItem<SnakeQueryData>,
+) -> SnakeState {
+    SnakeState {
+        client_id,
+        tail: tail.0.iter().copied().collect(),
+        length: length.clone(),
+        speed: speed.0,
+        acceleration: acceleration.0,
+        input: MovementInput::from_action_state(action),
+    }
+}
+
+/// Record the snakes that were actually spawned (respawns of players and bots), before their first tick
+fn record_spawns(
+    mut recorder: ResMut<ReplayRecorder>,
+    players: Query<&Player>,
+    snakes: Query<SnakeQueryData, Added<HasPlayer>>,
+) {
+    for snake in snakes.iter() {
+        let Ok(player) = players.get(snake.0.0) else {
+            continue;
+        };
+        recorder.events.push(ReplayEvent::Spawn(snake_state(player.id, snake)));
+    }
+}
+
+fn record_deaths(
+    mut recorder: ResMut<ReplayRecorder>,
+    mut collisions: EventReader<SnakeCollision>,
+    snakes: Query<&HasPlayer>,
+    players: Query<&Player>,
+) {
+    let client_id = |snake: Entity| snakes.get(snake).ok()
+        .and_then(|has_player| players.get(has_player.0).ok())
+        .map(|player| player.id);
+    for collision in collisions.read() {
+        if let (Some(killed), Some(killer)) = (client_id(collision.killed), client_id(collision.killer)) {
+            recorder.events.push(ReplayEvent::Death { killed, killer });
+        }
+    }
+}
+
+fn record_growth(
+    mut recorder: ResMut<ReplayRecorder>,
+    mut collisions: EventReader<FoodCollision>,
+    snakes: Query<(&HasPlayer, &TailLength)>,
+    players: Query<&Player>,
+) {
+    for collision in collisions.read() {
+        let Ok((has_player, length)) = snakes.get(collision.snake) else {
+            continue;
+        };
+        if let Ok(player) = players.get(has_player.0) {
+            recorder.events.push(ReplayEvent::Grow { client_id: player.id, target_size: length.target_size });
+        }
+    }
+}
+
+/// Record the inputs that were applied during this tick, and write the frame
+fn record_tick(
+    mut recorder: ResMut<ReplayRecorder>,
+    players: Query<&Player>,
+    snakes: Query<SnakeQueryData>,
+    food: Query<&Position, With<FoodMarker>>,
+) {
+    let recorder = recorder.as_mut();
+    for (has_player, _, _, _, _, action) in snakes.iter() {
+        let Ok(player) = players.get(has_player.0) else {
+            continue;
+        };
+        let input = MovementInput::from_action_state(action);
+        if recorder.inputs.insert(player.id, input) != Some(input) {
+            recorder.events.push(ReplayEvent::Movement { client_id: player.id, input });
+        }
+    }
+    if recorder.tick % recorder.snapshot_interval == 0 {
+        let mut snapshot = Snapshot {
+            players: players.iter()
+                .map(|player| PlayerState { client_id: player.id, name: player.name.clone() })
+                .collect(),
+            snakes: snakes.iter()
+                .filter_map(|snake| {
+                    let player = players.get(snake.0.0).ok()?;
+                    Some(snake_state(player.id, snake))
+                })
+                .collect(),
+            food: food.iter().map(|pos| pos.0).collect(),
+        };
+        // sort by client id, so that the snakes are always restored in the same order
+        snapshot.players.sort_by_key(|player| player.client_id);
+        snapshot.snakes.sort_by_key(|snake| snake.client_id);
+        recorder.events.push(ReplayEvent::Snapshot(snapshot));
+    }
+    if !recorder.events.is_empty() {
+        let frame = ReplayFrame {
+            tick: recorder.tick,
+            events: std::mem::take(&mut recorder.events),
+        };
+        let is_snapshot = recorder.tick % recorder.snapshot_interval == 0;
+        recorder.write(frame);
+        // flush regularly so that the file is usable even if the server crashes
+        if is_snapshot {
+            let _ = recorder.writer.flush().map_err(|e| error!(?e, "Failed to flush replay"));
+        }
+    }
+    recorder.tick += 1;
+}
@@ -59,10 +59,18 @@ pub(crate) fn update_collider(
 }
 
 
+/// Side of the head on which the friction ray was cast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrictionSide {
+    Left,
+    Right,
+}
+
 #[derive(Event, Debug, PartialEq)]
 pub struct SnakeFrictionEvent {
     pub main: Entity,
     pub other: Entity,
+    pub side: FrictionSide,
     pub distance: f32,
 }
 
@@ -93,23 +101,15 @@ pub(crate) fn snake_friction(
             false,
             filter
         );
-        if left_ray_cast.is_some() || right_ray_cast.is_some() {
-            let (distance, other) = left_ray_cast.map_or_else(
-                || (right_ray_cast.unwrap().time_of_impact, right_ray_cast.unwrap().entity),
-                |l| right_ray_cast.map_or_else(
-                    || (l.time_of_impact, l.entity),
-                    |r| if l.time_of_impact < r.time_of_impact {
-                        (l.time_of_impact, l.entity)
-                    } else {
-                        (r.time_of_impact, r.entity)
-                    }
-                )
-            );
-            trace!(?entity, ?distance, ?other, "Friction!");
+        // both sides of the head contribute to the friction
+        let hits = [(FrictionSide::Left, left_ray_cast), (FrictionSide::Right, right_ray_cast)];
+        for (side, hit) in hits.into_iter().filter_map(|(side, hit)| Some((side, hit?))) {
+            trace!(?entity, ?side, distance = ?hit.time_of_impact, other = ?hit.entity, "Friction!");
             writer.send(SnakeFrictionEvent {
                 main: entity,
-                other,
-                distance,
+                other: hit.entity,
+                side,
+                distance: hit.time_of_impact,
             });
         }
     }
@@ -144,7 +144,7 @@ mod tests {
         ]));
         let collider2 = Collider::from(SharedShape::polyline(points2.points_front_to_back(), None));
         app.world.entity_mut(snake2).insert((points2, collider2));
-        // snake3: vertical on the right of snake1, closer than snake 2
+        // snake3: vertical on the right of snake1
         let snake3 = app.world.spawn(SnakeBundle::default()).id();
         let points3 = TailPoints(VecDeque::from([
             (Vec2::new(MAX_FRICTION_DISTANCE / 2.0, 0.0), Direction::Up),
@@ -156,25 +156,35 @@ mod tests {
         app.update();
 
         let mut result = app.world.get_resource_mut::<Events<SnakeFrictionEvent>>().unwrap().drain().collect::<Vec<_>>();
-        result.sort_by(|a, b| a.main.partial_cmp(&b.main).unwrap());
+        result.sort_by(|a, b| (a.main, a.other).cmp(&(b.main, b.other)));
+        // snake1 has friction with the snakes on both sides
         let mut expected = vec![
+            SnakeFrictionEvent {
+                main: snake1,
+                other: snake2,
+                side: FrictionSide::Left,
+                distance: MAX_FRICTION_DISTANCE / 1.5,
+            },
             SnakeFrictionEvent {
                 main: snake1,
                 other: snake3,
+                side: FrictionSide::Right,
                 distance: MAX_FRICTION_DISTANCE / 2.0,
             },
             SnakeFrictionEvent {
                 main: snake2,
                 other: snake1,
+                side: FrictionSide::Right,
                 distance: MAX_FRICTION_DISTANCE / 1.5,
             },
             SnakeFrictionEvent {
                 main: snake3,
                 other: snake1,
+                side: FrictionSide::Left,
                 distance: MAX_FRICTION_DISTANCE / 2.0,
             }
         ];
-        expected.sort_by(|a, b| a.main.partial_cmp(&b.main).unwrap());
+        expected.sort_by(|a, b| (a.main, a.other).cmp(&(b.main, b.other)));
 
         assert_eq!(result, expected);
     }
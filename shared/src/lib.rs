@@ -6,6 +6,7 @@ pub mod network;
 pub mod movement;
 pub mod utils;
 pub mod map;
+pub mod replay;
 
 pub struct SharedPlugin;
 
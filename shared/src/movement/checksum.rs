@@ -0,0 +1,102 @@
+//! Checksum of the simulation state, used to detect desyncs between the server and the clients (or between a
+//! replay and the original match).
+//!
+//! We use FNV-1a over the bit patterns of the floats instead of the std `Hasher`s, so that the checksum does not
+//! depend on the platform or on the version of rust.
+use crate::network::protocol::prelude::*;
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+#[derive(Clone, Copy, Debug)]
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(FNV_OFFSET)
+    }
+}
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_f32(&mut self, value: f32) {
+        self.write(&value.to_bits().to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+}
+
+/// Checksum of the state of one snake
+pub fn snake_checksum(tail: &TailPoints, length: &TailLength, speed: &Speed, acceleration: &Acceleration) -> u64 {
+    let mut hasher = Fnv::default();
+    for (point, direction) in tail.0.iter() {
+        hasher.write_f32(point.x);
+        hasher.write_f32(point.y);
+        hasher.write(&[*direction as u8]);
+    }
+    hasher.write_f32(length.current_size);
+    hasher.write_f32(length.target_size);
+    hasher.write_f32(speed.0);
+    hasher.write_f32(acceleration.0);
+    hasher.0
+}
+
+/// Combine the checksums of all the snakes. The snakes are sorted by key (for example the client id of their
+/// player) so that the result does not depend on the order in which the entities were iterated
+pub fn combined_checksum(mut checksums: Vec<(u64, u64)>) -> u64 {
+    checksums.sort_unstable();
+    let mut hasher = Fnv::default();
+    for (key, checksum) in checksums {
+        hasher.write_u64(key);
+        hasher.write_u64(checksum);
+    }
+    hasher.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use bevy::prelude::*;
+
+    use super::*;
+
+    fn snake(head: Vec2) -> (TailPoints, TailLength, Speed, Acceleration) {
+        (
+            TailPoints(VecDeque::from([(head, Direction::Up), (head - Vec2::Y * 10.0, Direction::Up)])),
+            TailLength { current_size: 10.0, target_size: 10.0 },
+            Speed(1.0),
+            Acceleration(0.0),
+        )
+    }
+
+    #[test]
+    fn test_checksum() {
+        let (tail, length, speed, acceleration) = snake(Vec2::ZERO);
+        let checksum = snake_checksum(&tail, &length, &speed, &acceleration);
+        assert_eq!(checksum, snake_checksum(&tail.clone(), &length, &speed, &acceleration));
+
+        // a tiny difference changes the checksum
+        let (other_tail, ..) = snake(Vec2::new(0.0, f32::EPSILON));
+        let other = snake_checksum(&other_tail, &length, &speed, &acceleration);
+        assert_ne!(checksum, other);
+
+        // the combined checksum does not depend on the order of the snakes
+        assert_eq!(
+            combined_checksum(vec![(1, checksum), (2, other)]),
+            combined_checksum(vec![(2, other), (1, checksum)]),
+        );
+        assert_ne!(
+            combined_checksum(vec![(1, checksum), (2, other)]),
+            combined_checksum(vec![(1, other), (2, checksum)]),
+        );
+    }
+}
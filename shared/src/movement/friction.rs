@@ -0,0 +1,135 @@
+//! Friction model: snakes accelerate when they are close to other snakes, and decelerate otherwise.
+//!
+//! The friction of each side of the head (see `snake_friction`) is converted into an acceleration with a
+//! `FrictionCurve`, and the acceleration of the snake eases towards that target instead of jumping to it.
+use bevy::prelude::*;
+
+use crate::collision::collider::MAX_FRICTION_DISTANCE;
+use crate::movement::{ACCELERATION_RATIO, BASE_ACCELERATION};
+
+/// Below this difference, the acceleration snaps to its target (so that it stops changing and being replicated)
+const SNAP_EPSILON: f32 = 1e-4;
+/// Steepness of the `FrictionCurve::Exponential` curve
+const EXPONENTIAL_STEEPNESS: f32 = 3.0;
+
+/// How the friction decreases with the distance to the other snake
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect)]
+pub enum FrictionCurve {
+    #[default]
+    Linear,
+    Quadratic,
+    Exponential,
+}
+
+impl FrictionCurve {
+    /// Friction between 0.0 (at `MAX_FRICTION_DISTANCE` or further) and 1.0 (when touching the other snake)
+    pub fn friction(&self, distance: f32) -> f32 {
+        let closeness = (1.0 - distance / MAX_FRICTION_DISTANCE).clamp(0.0, 1.0);
+        match self {
+            FrictionCurve::Linear => closeness,
+            FrictionCurve::Quadratic => closeness * closeness,
+            FrictionCurve::Exponential => {
+                let far = (-EXPONENTIAL_STEEPNESS).exp();
+                ((-EXPONENTIAL_STEEPNESS * (1.0 - closeness)).exp() - far) / (1.0 - far)
+            }
+        }
+    }
+}
+
+/// Parameters of the friction model.
+///
+/// The movement is simulated on both the client and the server, so they must use the same settings.
+/// Insert it before adding the `MovementPlugin` to use other values than the default ones.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct FrictionSettings {
+    pub curve: FrictionCurve,
+    /// fraction of the remaining difference that is covered each tick when the acceleration increases
+    pub rise_rate: f32,
+    /// fraction of the remaining difference that is covered each tick when the acceleration decreases
+    pub decay_rate: f32,
+}
+
+impl Default for FrictionSettings {
+    fn default() -> Self {
+        Self {
+            curve: FrictionCurve::Linear,
+            rise_rate: 0.2,
+            decay_rate: 0.05,
+        }
+    }
+}
+
+impl FrictionSettings {
+    /// Acceleration that the snake should reach, given the distances to the snakes on each side of its head
+    pub fn target_acceleration(&self, distances: impl IntoIterator<Item=f32>) -> f32 {
+        let friction = distances.into_iter()
+            .map(|distance| self.curve.friction(distance))
+            .sum::<f32>()
+            .min(1.0);
+        if friction == 0.0 {
+            BASE_ACCELERATION
+        } else {
+            BASE_ACCELERATION.abs() * ACCELERATION_RATIO * friction
+        }
+    }
+
+    /// Move the acceleration one tick closer to the target
+    pub fn ease(&self, acceleration: f32, target: f32) -> f32 {
+        let rate = if target > acceleration { self.rise_rate } else { self.decay_rate };
+        let eased = acceleration + (target - acceleration) * rate.clamp(0.0, 1.0);
+        if (target - eased).abs() < SNAP_EPSILON {
+            target
+        } else {
+            eased
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_friction_curves() {
+        for curve in [FrictionCurve::Linear, FrictionCurve::Quadratic, FrictionCurve::Exponential] {
+            assert_eq!(curve.friction(0.0), 1.0, "{curve:?}");
+            assert_eq!(curve.friction(MAX_FRICTION_DISTANCE), 0.0, "{curve:?}");
+            assert_eq!(curve.friction(2.0 * MAX_FRICTION_DISTANCE), 0.0, "{curve:?}");
+        }
+        let half = MAX_FRICTION_DISTANCE / 2.0;
+        assert_eq!(FrictionCurve::Linear.friction(half), 0.5);
+        assert_eq!(FrictionCurve::Quadratic.friction(half), 0.25);
+        // the exponential curve stays low until the other snake is close
+        let exponential = FrictionCurve::Exponential.friction(half);
+        assert!(exponential > 0.0 && exponential < 0.25);
+    }
+
+    #[test]
+    fn test_both_sides_contribute() {
+        let settings = FrictionSettings::default();
+        let max = BASE_ACCELERATION.abs() * ACCELERATION_RATIO;
+        let quarter = MAX_FRICTION_DISTANCE * 0.75;
+        assert_eq!(settings.target_acceleration([]), BASE_ACCELERATION);
+        assert_eq!(settings.target_acceleration([quarter]), max * 0.25);
+        assert_eq!(settings.target_acceleration([quarter, quarter]), max * 0.5);
+        // the friction is capped
+        assert_eq!(settings.target_acceleration([0.0, 0.0]), max);
+    }
+
+    #[test]
+    fn test_ease() {
+        let settings = FrictionSettings { curve: FrictionCurve::Linear, rise_rate: 0.5, decay_rate: 0.25 };
+        assert_eq!(settings.ease(0.0, 0.01), 0.005);
+        assert!((settings.ease(0.01, 0.0) - 0.0075).abs() < f32::EPSILON);
+        // we snap to the target when we are close enough
+        assert_eq!(settings.ease(0.0, 0.0001), 0.0001);
+        assert_eq!(settings.ease(0.01, 0.01), 0.01);
+
+        // we converge to the target
+        let mut acceleration = BASE_ACCELERATION;
+        for _ in 0..100 {
+            acceleration = settings.ease(acceleration, 0.02);
+        }
+        assert_eq!(acceleration, 0.02);
+    }
+}
@@ -1,26 +1,54 @@
-use bevy::ecs::entity::EntityHashSet;
+use bevy::ecs::entity::EntityHashMap;
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::ActionState;
-use crate::collision::collider::{MAX_FRICTION_DISTANCE, snake_friction, SnakeFrictionEvent};
+use crate::collision::collider::{FrictionSide, SnakeFrictionEvent};
 
 use crate::network::protocol::components::snake::Direction;
 use crate::network::protocol::prelude::*;
 use crate::utils::query::Controlled;
 
+pub use friction::{FrictionCurve, FrictionSettings};
+
+pub mod checksum;
+mod friction;
+
 pub struct MovementPlugin;
 
+/// The simulation must give the same result on every machine (the client predicts its snake, and replays
+/// re-simulate the match), so:
+/// - the systems inside `Movement` are chained
+/// - each system only updates one snake at a time from its own state, or combines the state of several snakes
+///   in a way that does not depend on the iteration order (see `update_acceleration`)
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub enum SimulationSet {
     // move snakes
     Movement,
 }
 
+/// Snakes with this component do not move (for example while their player is reconnecting)
+#[derive(Component, Debug, Default)]
+pub struct Frozen;
+
 pub const MIN_SPEED: f32 = 1.0;
 pub const MAX_SPEED: f32 = 4.0;
+/// Maximum speed while boosting
+pub const MAX_BOOST_SPEED: f32 = 6.0;
+/// Change of speed per tick when boosting, or when coming back to `MAX_SPEED` after a boost
+pub const BOOST_ACCELERATION: f32 = 0.05;
+/// Deceleration that is added when braking
+pub const BRAKE_DECELERATION: f32 = 0.05;
+/// Length burned per tick while boosting
+pub const BOOST_COST: f32 = 0.5;
+/// Snakes cannot boost when they are shorter than this
+pub const MIN_BOOST_LENGTH: f32 = 100.0;
+/// Tails are never shorter than this (cutting a tail to 0 would remove all its points, see `TailPoints::shorten_by`)
+pub const MIN_TAIL_LENGTH: f32 = 10.0;
 
 
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
+        // resources
+        app.init_resource::<FrictionSettings>();
         // events
         app.add_event::<SnakeFrictionEvent>();
 
@@ -33,63 +61,72 @@ impl Plugin for MovementPlugin {
         // 4. update heads: integrate acceleration into velocity, integrate velocity into position
         // 5. update the back of the tails: shorten tail
         app.add_systems(FixedUpdate,
-                        (turn_heads, update_acceleration.after(snake_friction), update_tails)
+                        (turn_heads, update_acceleration, update_tails)
                             .chain()
                             .in_set(SimulationSet::Movement)
         );
+
+        // reflect
+        app.register_type::<FrictionSettings>();
     }
 }
 
 // 1. turn heads according to input
 pub fn turn_heads(
-    mut query: Query<(&mut TailPoints, &ActionState<PlayerMovement>), Controlled>,
+    mut query: Query<(&mut TailPoints, &ActionState<PlayerMovement>), (Controlled, Without<Frozen>)>,
 ) {
     for (mut tail, input) in query.iter_mut() {
-        let direction = tail.0.front().unwrap().1;
-        if input.pressed(&PlayerMovement::Up) {
-            if direction != Direction::Down && direction != Direction::Up {
-                tail.0.front_mut().unwrap().1 = Direction::Up;
-            }
-        } else if input.pressed(&PlayerMovement::Down) {
-            if direction != Direction::Down && direction != Direction::Up {
-                tail.0.front_mut().unwrap().1 = Direction::Down;
-            }
-        } else if input.pressed(&PlayerMovement::Left) {
-            if direction != Direction::Left && direction != Direction::Right {
-                tail.0.front_mut().unwrap().1 = Direction::Left;
-            }
-        } else if input.pressed(&PlayerMovement::Right) {
-            if direction != Direction::Left && direction != Direction::Right {
-                tail.0.front_mut().unwrap().1 = Direction::Right;
-            }
+        if let Some(direction) = turn_direction(input, tail.front().1) {
+            tail.front_mut().1 = direction;
         }
     }
 }
 
+/// Returns the new direction of the head if the input makes the snake turn.
+/// Snakes can only turn by 90 degrees, so inputs along the current axis are ignored
+pub fn turn_direction(input: &ActionState<PlayerMovement>, direction: Direction) -> Option<Direction> {
+    let vertical = direction == Direction::Up || direction == Direction::Down;
+    if input.pressed(&PlayerMovement::Up) {
+        (!vertical).then_some(Direction::Up)
+    } else if input.pressed(&PlayerMovement::Down) {
+        (!vertical).then_some(Direction::Down)
+    } else if input.pressed(&PlayerMovement::Left) {
+        vertical.then_some(Direction::Left)
+    } else if input.pressed(&PlayerMovement::Right) {
+        vertical.then_some(Direction::Right)
+    } else {
+        None
+    }
+}
+
 pub const BASE_ACCELERATION: f32 = -0.01;
 pub const ACCELERATION_RATIO: f32 = 2.0;
 
 // 2. update acceleration (are there close snakes?)
-// - we start accelerating when we are close to another snake
+// - we accelerate when we are close to other snakes (on either side of the head)
 // - otherwise we keep decelerating until we reach minimum speed
-// - i'd like to add some easing; i.e have the change in acceleration not take place instantly
+// - the acceleration eases towards its target instead of changing instantly (see `FrictionSettings`)
 pub fn update_acceleration(
+    settings: Res<FrictionSettings>,
     mut events: EventReader<SnakeFrictionEvent>,
     mut snakes: Query<(Entity, &mut Acceleration), Controlled>
 ) {
-    let mut accelerating_snakes = EntityHashSet::default();
+    // the friction events are sent in Update, so a tick can read the events of several frames: we only keep the
+    // closest distance per side of the head, so that the same side is never counted twice, and the result does not
+    // depend on the order of the events
+    let mut distances = EntityHashMap::<[Option<f32>; 2]>::default();
     for event in events.read() {
-        let Ok((_, mut acceleration)) = snakes.get_mut(event.main) else {
-            continue;
+        let side = match event.side {
+            FrictionSide::Left => 0,
+            FrictionSide::Right => 1,
         };
-        accelerating_snakes.insert(event.main);
-        acceleration.set_if_neq(Acceleration(BASE_ACCELERATION.abs() * ACCELERATION_RATIO * (MAX_FRICTION_DISTANCE - event.distance) / MAX_FRICTION_DISTANCE));
+        let distance = &mut distances.entry(event.main).or_default()[side];
+        *distance = Some(distance.map_or(event.distance, |d| d.min(event.distance)));
     }
-    // TODO: we'd like to add easing to this
     for (entity, mut acceleration) in snakes.iter_mut() {
-        if !accelerating_snakes.contains(&entity) {
-            acceleration.set_if_neq(Acceleration(BASE_ACCELERATION));
-        }
+        let sides = distances.remove(&entity).unwrap_or_default();
+        let target = settings.target_acceleration(sides.into_iter().flatten());
+        acceleration.set_if_neq(Acceleration(settings.ease(acceleration.0, target)));
     }
 }
 
@@ -97,9 +134,12 @@ pub fn update_acceleration(
 // 4. update acceleration and speed
 // 5. update the back of the tails: shorten tail
 pub fn update_tails(
-    mut query: Query<(&mut TailPoints, &mut TailLength, &mut Speed, &Acceleration), Controlled>
+    mut query: Query<
+        (&mut TailPoints, &mut TailLength, &mut Speed, &Acceleration, Option<&ActionState<PlayerMovement>>),
+        (Controlled, Without<Frozen>)
+    >
 ) {
-    for (mut tail, mut length, mut speed, acceleration) in query.iter_mut() {
+    for (mut tail, mut length, mut speed, acceleration, action) in query.iter_mut() {
         // 3. update front of the tail: possibly add a new inflection point if necessary
         if tail.is_changed() {
             // copy the first point to the front when we have a turn
@@ -108,13 +148,14 @@ pub fn update_tails(
         }
 
         // 4. update acceleration and speed
-        // update velocity
-        // do not update speed if we are at min speed and acceleration is negative
-        // do not update speed if we are at max speed and acceleration is positive
-        if !((acceleration.0 < 0.0 && speed.0 == MIN_SPEED) || (acceleration.0 > 0.0 && speed.0 == MAX_SPEED)) {
-            speed.0 += acceleration.0;
-            speed.0 = speed.0.max(MIN_SPEED).min(MAX_SPEED);
+        // boosting burns some of the length of the snake
+        let boost = boost_cost(action, &length);
+        if boost > 0.0 {
+            length.target_size -= boost;
         }
+        let brake = action.is_some_and(|action| action.pressed(&PlayerMovement::Brake));
+        let new_speed = next_speed(speed.0, acceleration.0, boost > 0.0, brake);
+        speed.set_if_neq(Speed(new_speed));
 
         // update position
         tail.0.front_mut().map(|(pos, dir)| *pos += dir.delta() * speed.0);
@@ -126,6 +167,36 @@ pub fn update_tails(
     }
 }
 
+/// Length that the snake burns this tick because it is boosting (0.0 if it is not boosting).
+/// Braking cancels the boost, and short snakes cannot boost
+pub fn boost_cost(action: Option<&ActionState<PlayerMovement>>, length: &TailLength) -> f32 {
+    let Some(action) = action else {
+        return 0.0;
+    };
+    if action.pressed(&PlayerMovement::Boost)
+        && !action.pressed(&PlayerMovement::Brake)
+        && length.target_size - BOOST_COST >= MIN_BOOST_LENGTH {
+        BOOST_COST
+    } else {
+        0.0
+    }
+}
+
+/// Integrate the acceleration into the speed.
+/// - boosting accelerates up to `MAX_BOOST_SPEED`, regardless of the friction
+/// - after a boost, the speed comes back to `MAX_SPEED` gradually
+/// - braking adds a deceleration on top of the friction
+pub fn next_speed(speed: f32, acceleration: f32, boost: bool, brake: bool) -> f32 {
+    if boost {
+        return (speed + BOOST_ACCELERATION).min(MAX_BOOST_SPEED);
+    }
+    let acceleration = if brake { acceleration - BRAKE_DECELERATION } else { acceleration };
+    if speed > MAX_SPEED {
+        return (speed - BOOST_ACCELERATION + acceleration.min(0.0)).max(MAX_SPEED);
+    }
+    (speed + acceleration).clamp(MIN_SPEED, MAX_SPEED)
+}
+
 /// Shorten the tail to match the target size
 pub fn shorten_tail(tail: &mut TailPoints, tail_length: &mut TailLength) {
     // if we still need to grow the tail, do nothing
@@ -205,7 +276,98 @@ mod tests {
                    ])));
         assert_eq!(app.world.entity(snake).get::<TailLength>().unwrap().current_size, 30.0);
     }
-}
 
+    #[test]
+    fn test_update_acceleration() {
+        let mut app = App::new();
+        app.init_resource::<FrictionSettings>();
+        app.add_event::<SnakeFrictionEvent>();
+        app.add_systems(Update, update_acceleration);
+        let snake = create_snake(&mut app);
+        let other = create_snake(&mut app);
+        let settings = FrictionSettings::default();
 
+        // friction on both sides: the acceleration eases towards the target
+        let target = settings.target_acceleration([5.0, 10.0]);
+        app.world.send_event(SnakeFrictionEvent { main: snake, other, side: FrictionSide::Left, distance: 5.0 });
+        app.world.send_event(SnakeFrictionEvent { main: snake, other, side: FrictionSide::Right, distance: 10.0 });
+        app.update();
+        let acceleration = app.world.entity(snake).get::<Acceleration>().unwrap().0;
+        assert_eq!(acceleration, settings.ease(0.0, target));
+        assert!(acceleration > 0.0 && acceleration < target);
+
+        // no friction: the acceleration decays towards the base acceleration
+        app.update();
+        assert_eq!(app.world.entity(snake).get::<Acceleration>().unwrap().0, settings.ease(acceleration, BASE_ACCELERATION));
+        assert_eq!(app.world.entity(other).get::<Acceleration>().unwrap().0, settings.ease(settings.ease(0.0, BASE_ACCELERATION), BASE_ACCELERATION));
+    }
 
+    #[test]
+    fn test_update_acceleration_duplicate_events() {
+        let mut app = App::new();
+        app.init_resource::<FrictionSettings>();
+        app.add_event::<SnakeFrictionEvent>();
+        app.add_systems(Update, update_acceleration);
+        let snake = create_snake(&mut app);
+        let other = create_snake(&mut app);
+        let settings = FrictionSettings::default();
+
+        // the same side reported twice (events from two frames) counts once
+        app.world.send_event(SnakeFrictionEvent { main: snake, other, side: FrictionSide::Left, distance: 10.0 });
+        app.world.send_event(SnakeFrictionEvent { main: snake, other, side: FrictionSide::Left, distance: 10.0 });
+        app.update();
+        let target = settings.target_acceleration([10.0]);
+        assert_eq!(app.world.entity(snake).get::<Acceleration>().unwrap().0, settings.ease(0.0, target));
+    }
+
+    #[test]
+    fn test_next_speed() {
+        // friction and min/max speed
+        assert_eq!(next_speed(2.0, 0.5, false, false), 2.5);
+        assert_eq!(next_speed(MAX_SPEED, 0.5, false, false), MAX_SPEED);
+        assert_eq!(next_speed(MIN_SPEED, BASE_ACCELERATION, false, false), MIN_SPEED);
+        // boosting goes above the max speed, until the max boost speed
+        assert_eq!(next_speed(MAX_SPEED, 0.0, true, false), MAX_SPEED + BOOST_ACCELERATION);
+        assert_eq!(next_speed(MAX_BOOST_SPEED, 0.0, true, false), MAX_BOOST_SPEED);
+        // after a boost, we come back to the max speed gradually
+        assert_eq!(next_speed(5.0, 0.0, false, false), 5.0 - BOOST_ACCELERATION);
+        assert_eq!(next_speed(MAX_SPEED + 0.01, 0.0, false, false), MAX_SPEED);
+        // braking slows down faster than the friction
+        assert_eq!(next_speed(2.0, 0.0, false, true), 2.0 - BRAKE_DECELERATION);
+        assert_eq!(next_speed(MIN_SPEED, 0.0, false, true), MIN_SPEED);
+    }
+
+    #[test]
+    fn test_boost_cost() {
+        let long = TailLength { current_size: 150.0, target_size: 150.0 };
+        let short = TailLength { current_size: MIN_BOOST_LENGTH, target_size: MIN_BOOST_LENGTH };
+        let mut action = ActionState::<PlayerMovement>::default();
+        assert_eq!(boost_cost(None, &long), 0.0);
+        assert_eq!(boost_cost(Some(&action), &long), 0.0);
+
+        action.press(&PlayerMovement::Boost);
+        assert_eq!(boost_cost(Some(&action), &long), BOOST_COST);
+        // short snakes cannot boost
+        assert_eq!(boost_cost(Some(&action), &short), 0.0);
+        // braking cancels the boost
+        action.press(&PlayerMovement::Brake);
+        assert_eq!(boost_cost(Some(&action), &long), 0.0);
+    }
+
+    #[test]
+    fn test_boost_burns_length() {
+        let mut app = App::new();
+        app.add_systems(Update, update_tails);
+        let snake = create_snake(&mut app);
+        let mut action = ActionState::<PlayerMovement>::default();
+        action.press(&PlayerMovement::Boost);
+        app.world.entity_mut(snake).insert((Speed(MAX_SPEED), action));
+
+        app.update();
+
+        assert_eq!(app.world.entity(snake).get::<Speed>().unwrap().0, MAX_SPEED + BOOST_ACCELERATION);
+        let length = app.world.entity(snake).get::<TailLength>().unwrap();
+        assert_eq!(length.target_size, 150.0 - BOOST_COST);
+        assert_eq!(length.current_size, 150.0 - BOOST_COST);
+    }
+}
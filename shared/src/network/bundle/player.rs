@@ -16,13 +16,20 @@ impl PlayerBundle {
     pub fn new(player: Player) -> Self {
         Self { player, action: ActionState::default() }
     }
-    pub fn spawn(self, commands: &mut Commands, client_id: ClientId) -> Entity {
+    /// Replication settings of the player entity of `client_id`
+    pub fn replicate(client_id: ClientId) -> Replicate {
         let mut replicate = Replicate {
             replication_group: ReplicationGroup::new_id(client_id),
+            // the snake is a child of the player, but it has its own replication targets
+            replicate_hierarchy: false,
             ..default()
         };
         // no need to replicate player inputs
         replicate.disable_component::<ActionState<DeadGameAction>>();
-        commands.spawn((self, replicate)).id()
+        replicate
+    }
+
+    pub fn spawn(self, commands: &mut Commands, client_id: ClientId) -> Entity {
+        commands.spawn((self, PlayerBundle::replicate(client_id))).id()
     }
 }
\ No newline at end of file
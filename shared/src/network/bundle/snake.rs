@@ -36,17 +36,29 @@ pub struct SnakeBundle {
 
 impl Default for SnakeBundle {
     fn default() -> Self {
+        Self::new(Vec2::ZERO)
+    }
+}
+
+impl SnakeBundle {
+    /// Create a snake whose head is at `head`, pointing up
+    pub fn new(head: Vec2) -> Self {
         let tail_points = TailPoints(VecDeque::from([
-            (Vec2::new(0.0, 0.0), Direction::Up),
-            (Vec2::new(0.0, 0.0) + Direction::Down.delta() * TAIL_SIZE, Direction::Up),
+            (head, Direction::Up),
+            (head + Direction::Down.delta() * TAIL_SIZE, Direction::Up),
         ]));
+        Self::from_tail(tail_points, TailLength {
+            current_size: TAIL_SIZE,
+            target_size: TAIL_SIZE,
+        })
+    }
+
+    /// Create a snake with an existing tail (for example when restoring a snapshot)
+    pub fn from_tail(tail_points: TailPoints, tail_length: TailLength) -> Self {
         let collider = Collider::from(SharedShape::polyline(tail_points.points_front_to_back(), None));
         Self {
             tail_points,
-            tail_length: TailLength {
-                current_size: TAIL_SIZE,
-                target_size: TAIL_SIZE,
-            },
+            tail_length,
             speed: Speed(MIN_SPEED),
             acceleration: Acceleration(0.0),
             position: Position::default(),
@@ -56,9 +68,7 @@ impl Default for SnakeBundle {
             action: ActionState::default(),
         }
     }
-}
 
-impl SnakeBundle {
     // pub(crate) fn spawn(commands: &mut Commands) {
     //     let mut head_id = commands.spawn(HeadBundle::default());
     //     head_id.with_children(|parent| {
@@ -66,7 +76,8 @@ impl SnakeBundle {
     //     });
     // }
 
-    pub fn spawn(commands: &mut Commands, client_id: ClientId) -> Entity {
+    /// Replication settings of a snake controlled by `client_id`
+    pub fn replicate(client_id: ClientId) -> Replicate {
         let mut replicate = Replicate {
             prediction_target: NetworkTarget::Single(client_id),
             interpolation_target: NetworkTarget::AllExceptSingle(client_id),
@@ -75,10 +86,25 @@ impl SnakeBundle {
         };
         // we do not need to replicate the player's actions
         replicate.disable_component::<ActionState<PlayerMovement>>();
+        replicate
+    }
+
+    /// Replication settings of a snake controlled by the server: every client interpolates it
+    pub fn replicate_server_owned(group_id: u64) -> Replicate {
+        let mut replicate = Replicate {
+            interpolation_target: NetworkTarget::All,
+            replication_group: ReplicationGroup::new_id(group_id),
+            ..default()
+        };
+        replicate.disable_component::<ActionState<PlayerMovement>>();
+        replicate
+    }
+
+    pub fn spawn(commands: &mut Commands, client_id: ClientId) -> Entity {
         let head_entity = commands.spawn(
             (
                 SnakeBundle::default(),
-                replicate,
+                SnakeBundle::replicate(client_id),
             )
         ).id();
         head_entity
@@ -25,4 +25,86 @@ pub enum Transports {
     Udp,
     WebTransport,
     WebSocket,
-}
\ No newline at end of file
+}
+/// Named network conditions, to test the game with the kind of connection that players have
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum LinkPreset {
+    /// no added latency or loss
+    #[default]
+    None,
+    Lan,
+    Wifi,
+    Mobile,
+    Awful,
+}
+
+impl LinkPreset {
+    pub const ALL: [LinkPreset; 5] = [LinkPreset::None, LinkPreset::Lan, LinkPreset::Wifi, LinkPreset::Mobile, LinkPreset::Awful];
+
+    pub fn settings(&self) -> LinkSettings {
+        let (latency, jitter, loss) = match self {
+            LinkPreset::None => (0, 0, 0.0),
+            LinkPreset::Lan => (2, 1, 0.0),
+            LinkPreset::Wifi => (20, 5, 0.005),
+            LinkPreset::Mobile => (80, 20, 0.02),
+            LinkPreset::Awful => (250, 60, 0.1),
+        };
+        LinkSettings {
+            latency: Duration::from_millis(latency),
+            jitter: Duration::from_millis(jitter),
+            loss,
+        }
+    }
+}
+
+/// Latency, jitter and packet loss added to the incoming packets
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct LinkSettings {
+    pub latency: Duration,
+    pub jitter: Duration,
+    /// probability of dropping a packet, between 0 and 1
+    pub loss: f32,
+}
+
+impl LinkSettings {
+    pub fn conditioner(&self) -> LinkConditionerConfig {
+        LinkConditionerConfig {
+            incoming_latency: self.latency,
+            incoming_jitter: self.jitter,
+            incoming_loss: self.loss,
+        }
+    }
+}
+
+/// Command-line options of the link conditioner, shared by the client and the server.
+/// The options override the values of the preset
+#[derive(clap::Args, Clone, Debug, Default, PartialEq)]
+pub struct LinkArgs {
+    /// Simulate the network conditions of a preset
+    #[arg(long, value_enum)]
+    pub link_preset: Option<LinkPreset>,
+
+    /// Added latency, in milliseconds
+    #[arg(long)]
+    pub latency: Option<u64>,
+
+    /// Added jitter, in milliseconds
+    #[arg(long)]
+    pub jitter: Option<u64>,
+
+    /// Probability of losing a packet, between 0 and 1
+    #[arg(long)]
+    pub loss: Option<f32>,
+}
+
+impl LinkArgs {
+    /// Settings of the link conditioner, using `default` for the values that are not set
+    pub fn settings(&self, default: LinkSettings) -> LinkSettings {
+        let base = self.link_preset.map_or(default, |preset| preset.settings());
+        LinkSettings {
+            latency: self.latency.map_or(base.latency, Duration::from_millis),
+            jitter: self.jitter.map_or(base.jitter, Duration::from_millis),
+            loss: self.loss.map_or(base.loss, |loss| loss.clamp(0.0, 1.0)),
+        }
+    }
+}
@@ -1,5 +1,4 @@
-use bevy::app::App;
-use bevy::prelude::Plugin;
+use bevy::prelude::*;
 
 use protocol::prelude::*;
 
@@ -14,12 +13,29 @@ impl Plugin for NetworkPlugin {
     fn build(&self, app: &mut App) {
         // events
         app.add_event::<SnakeCollision>();
+        // systems
+        app.add_systems(Update, attach_snake_to_player);
         // registry
         app.register_type::<TailLength>()
             .register_type::<TailPoints>()
+            .register_type::<protocol::prelude::Direction>()
             .register_type::<Speed>()
             .register_type::<Acceleration>()
             .register_type::<HasPlayer>()
-            .register_type::<Player>();
+            .register_type::<Player>()
+            .register_type::<Spectator>()
+            .register_type::<SnakeAppearance>();
+    }
+}
+
+/// Make each snake a child of its player, so that despawning the player despawns the snake
+pub fn attach_snake_to_player(
+    mut commands: Commands,
+    snakes: Query<(Entity, &HasPlayer), Added<HasPlayer>>,
+) {
+    for (snake, has_player) in snakes.iter() {
+        if let Some(mut player) = commands.get_entity(has_player.0) {
+            player.add_child(snake);
+        }
     }
 }
@@ -26,6 +26,10 @@ pub enum Components {
     // player
     #[sync(simple)]
     Player(player::Player),
+    #[sync(once)]
+    Spectator(player::Spectator),
+    #[sync(simple)]
+    SnakeAppearance(player::SnakeAppearance),
     // food
     #[sync(once)]
     FoodMarker(food::FoodMarker),
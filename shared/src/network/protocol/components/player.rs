@@ -1,18 +1,48 @@
-use bevy::prelude::{Component, Entity, EntityMapper, Reflect};
-use lightyear::prelude::{ClientId, LightyearMapEntities, Message};
+use bevy::prelude::{Color, Component, Reflect};
+use clap::ValueEnum;
+use lightyear::prelude::{ClientId, Message};
 use serde::{Deserialize, Serialize};
 
+/// The player's snake is a child of the player entity (see [`HasPlayer`](super::snake::HasPlayer))
 #[derive(Component, Message, Deserialize, Serialize, Clone, Debug, PartialEq, Reflect)]
-#[message(custom_map)]
 pub struct Player{
     pub id: ClientId,
     pub name: String,
-    pub snake: Option<Entity>,
 }
 
-impl LightyearMapEntities for Player {
-    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
-        self.snake.map(|x| entity_mapper.map_entity(x));
+
+/// Marker for players that only watch the match: they have no snake and cannot spawn one
+#[derive(Component, Message, Deserialize, Serialize, Clone, Debug, PartialEq, Reflect)]
+pub struct Spectator;
+
+/// Cosmetic look of the player's snake.
+/// The server picks it every time the snake spawns, so that nearby snakes don't have similar colors
+#[derive(Component, Message, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct SnakeAppearance {
+    /// hue of the snake's color, in degrees
+    pub hue: f32,
+    pub pattern: SnakePattern,
+    pub head: HeadStyle,
+}
+
+impl SnakeAppearance {
+    pub fn color(&self) -> Color {
+        Color::hsl(self.hue, 0.7, 0.55)
     }
 }
 
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, ValueEnum)]
+pub enum SnakePattern {
+    #[default]
+    Solid,
+    Striped,
+    Dotted,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, ValueEnum)]
+pub enum HeadStyle {
+    #[default]
+    Arrow,
+    Round,
+    Square,
+}
@@ -38,7 +38,10 @@ pub struct TailLength{
 // tail inflection points, from front (head point) to back (tail end point)
 pub struct TailPoints(pub VecDeque<(Vec2, Direction)>);
 
-// TODO: replace this with Parent in bevy 0.13
+/// Replicated link from a snake to its player entity.
+/// On both the server and the client the snake is made a child of the player entity when this component is added,
+/// so that despawning the player also despawns its snake. We do not rely on lightyear's hierarchy replication
+/// because the snake and the player have different replication targets.
 #[derive(Component, Message, Deserialize, Serialize, Clone, Debug, PartialEq, Reflect)]
 #[message(custom_map)]
 pub struct HasPlayer(pub Entity);
@@ -46,7 +49,6 @@ pub struct HasPlayer(pub Entity);
 impl LightyearMapEntities for HasPlayer {
     fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
         self.0 = entity_mapper.map_entity(self.0);
-
     }
 }
 
@@ -3,12 +3,30 @@ use leafwing_input_manager::prelude::*;
 use lightyear::prelude::LeafwingUserAction;
 use serde::{Deserialize, Serialize};
 
+use crate::network::protocol::components::snake::Direction;
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Reflect, Actionlike)]
 pub enum PlayerMovement {
     Up,
     Down,
     Left,
     Right,
+    /// burn length to go faster than `MAX_SPEED`
+    Boost,
+    /// slow down faster
+    Brake,
 }
 
-impl LeafwingUserAction for PlayerMovement {}
\ No newline at end of file
+impl LeafwingUserAction for PlayerMovement {}
+
+/// Input that turns a snake towards `direction`
+impl From<Direction> for PlayerMovement {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Up => PlayerMovement::Up,
+            Direction::Down => PlayerMovement::Down,
+            Direction::Left => PlayerMovement::Left,
+            Direction::Right => PlayerMovement::Right,
+        }
+    }
+}
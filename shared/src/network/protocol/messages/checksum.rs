@@ -0,0 +1,20 @@
+use bevy::prelude::{Entity, EntityMapper, Event};
+use lightyear::prelude::{LightyearMapEntities, Message, Tick};
+use serde::{Deserialize, Serialize};
+
+/// Sent periodically by the server: checksum of each snake at `tick` (see `shared::movement::checksum`),
+/// so that the clients can detect when their confirmed state diverges from the server's state
+#[derive(Message, Event, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[message(custom_map)]
+pub struct StateChecksum {
+    pub tick: Tick,
+    pub snakes: Vec<(Entity, u64)>,
+}
+
+impl LightyearMapEntities for StateChecksum {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        for (entity, _) in self.snakes.iter_mut() {
+            *entity = entity_mapper.map_entity(*entity);
+        }
+    }
+}
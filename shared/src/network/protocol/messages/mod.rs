@@ -4,9 +4,18 @@ use super::GameProtocol;
 
 pub(crate) mod snake;
 pub(crate) mod food;
+pub(crate) mod session;
+pub(crate) mod checksum;
+pub(crate) mod player;
 
 #[message_protocol(protocol = GameProtocol)]
 pub enum Messages {
     SnakeCollision(snake::SnakeCollision),
     FoodCollision(food::FoodCollision),
+    SessionToken(session::SessionToken),
+    ResumeSession(session::ResumeSession),
+    Kicked(session::Kicked),
+    StateChecksum(checksum::StateChecksum),
+    Join(player::Join),
+    SkinPreference(player::SkinPreference),
 }
\ No newline at end of file
@@ -0,0 +1,22 @@
+use bevy::prelude::Event;
+use lightyear::prelude::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::network::protocol::components::player::{HeadStyle, SnakePattern};
+
+/// Sent by a client every time it connects, after `ResumeSession` if it has a session token.
+/// The server spawns the snake of a new player when it receives this message, unless the client wants to watch the
+/// match instead of playing (or the server is full)
+#[derive(Message, Event, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Join {
+    pub spectate: bool,
+}
+
+/// Sent by a client when it connects, to choose the look of its snake.
+/// The server may pick another hue if the preferred one is too close to the hue of a nearby snake
+#[derive(Message, Event, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SkinPreference {
+    pub hue: Option<f32>,
+    pub pattern: SnakePattern,
+    pub head: HeadStyle,
+}
@@ -0,0 +1,23 @@
+use bevy::prelude::Event;
+use lightyear::prelude::Message;
+use serde::{Deserialize, Serialize};
+
+/// Sent by the server when a client connects; the client can use the token to resume its session
+/// if it gets disconnected
+#[derive(Message, Event, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SessionToken {
+    pub token: u64,
+}
+
+/// Sent by a reconnecting client to take back its player and snake
+#[derive(Message, Event, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ResumeSession {
+    pub token: u64,
+}
+
+/// Sent by the server right before it disconnects a client on purpose (for example because of input violations).
+/// The client should not reconnect automatically, and its session cannot be resumed
+#[derive(Message, Event, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Kicked {
+    pub reason: String,
+}
@@ -21,6 +21,9 @@ pub mod prelude {
     // messages
     pub use super::messages::snake::*;
     pub use super::messages::food::*;
+    pub use super::messages::session::*;
+    pub use super::messages::checksum::*;
+    pub use super::messages::player::*;
     // inputs
     pub use super::inputs::PlayerMovement;
     pub use super::inputs::DeadGameAction;
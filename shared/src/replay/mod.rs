@@ -0,0 +1,256 @@
+//! Replay file format.
+//!
+//! A replay file starts with `REPLAY_MAGIC`, followed by the format version (u16, little-endian)
+//! and a bincode-encoded `ReplayHeader` (with varint integers). The rest of the file is a stream of bincode-encoded `ReplayFrame`s.
+//! Frames are only written for ticks where something happened, and inputs are only written when they change,
+//! which keeps the files small. Snapshots of the full state are written periodically so that a replay can be
+//! started from any snapshot (they are the keyframes used for seeking).
+//!
+//! Because the frames are streamed, a replay whose recording was interrupted can still be read up to its last
+//! complete frame.
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use bincode::Options;
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+use lightyear::prelude::ClientId;
+use serde::{Deserialize, Serialize};
+
+use crate::network::protocol::prelude::*;
+use crate::network::protocol::prelude::Direction;
+use crate::network::protocol::PlayerMovement;
+
+pub const REPLAY_MAGIC: [u8; 4] = *b"LRRP";
+/// Bump this every time the format of the header or the frames changes
+/// (2: spawn events, 3: growth events, 4: boost/brake bits in the movement inputs)
+pub const REPLAY_VERSION: u16 = 4;
+pub const REPLAY_EXTENSION: &str = "replay";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplayHeader {
+    /// seed of the `GlobalRng` of the server
+    pub seed: u64,
+    pub tick_rate: f64,
+    /// unix timestamp of the start of the match, in seconds
+    pub start_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplayFrame {
+    /// number of ticks since the start of the recording
+    pub tick: u32,
+    pub events: Vec<ReplayEvent>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ReplayEvent {
+    Connect(ClientId),
+    Disconnect(ClientId),
+    /// the movement keys held by the client changed
+    Movement {
+        client_id: ClientId,
+        input: MovementInput,
+    },
+    /// a snake was spawned (for a client or a bot), with its state before its first tick
+    Spawn(SnakeState),
+    Death {
+        killed: ClientId,
+        killer: ClientId,
+    },
+    /// the snake of the client ate food; growth is not simulated by the viewer, so we store the new target size
+    Grow {
+        client_id: ClientId,
+        target_size: f32,
+    },
+    Snapshot(Snapshot),
+}
+
+/// Set of pressed `PlayerMovement` keys, stored as a bitmask
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MovementInput(pub u8);
+
+const MOVEMENTS: [PlayerMovement; 6] = [
+    PlayerMovement::Up,
+    PlayerMovement::Down,
+    PlayerMovement::Left,
+    PlayerMovement::Right,
+    PlayerMovement::Boost,
+    PlayerMovement::Brake,
+];
+
+impl MovementInput {
+    pub fn from_action_state(action: &ActionState<PlayerMovement>) -> Self {
+        Self(MOVEMENTS.iter().enumerate()
+            .filter(|(_, movement)| action.pressed(movement))
+            .fold(0, |mask, (i, _)| mask | (1 << i)))
+    }
+
+    /// Overwrite the pressed keys of `action` with this input
+    pub fn apply(&self, action: &mut ActionState<PlayerMovement>) {
+        for (i, movement) in MOVEMENTS.iter().enumerate() {
+            if self.0 & (1 << i) != 0 {
+                action.press(movement);
+            } else {
+                action.release(movement);
+            }
+        }
+    }
+}
+
+/// Full state of the match
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub players: Vec<PlayerState>,
+    pub snakes: Vec<SnakeState>,
+    pub food: Vec<Vec2>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlayerState {
+    pub client_id: ClientId,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SnakeState {
+    pub client_id: ClientId,
+    pub tail: Vec<(Vec2, Direction)>,
+    pub length: TailLength,
+    pub speed: f32,
+    pub acceleration: f32,
+    pub input: MovementInput,
+}
+
+/// Integers are varint-encoded, which makes the frames much smaller (ticks, client ids and bitmasks are small)
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+pub struct ReplayWriter {
+    writer: BufWriter<File>,
+}
+
+impl ReplayWriter {
+    pub fn create(path: &Path, header: &ReplayHeader) -> Result<Self> {
+        let file = File::create(path).with_context(|| format!("could not create replay file {path:?}"))?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(&REPLAY_MAGIC)?;
+        writer.write_all(&REPLAY_VERSION.to_le_bytes())?;
+        options().serialize_into(&mut writer, header)?;
+        Ok(Self { writer })
+    }
+
+    pub fn write_frame(&mut self, frame: &ReplayFrame) -> Result<()> {
+        options().serialize_into(&mut self.writer, frame)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("could not open replay file {path:?}"))?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    pub fn from_reader(mut reader: impl Read) -> Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != REPLAY_MAGIC {
+            bail!("not a replay file");
+        }
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        let version = u16::from_le_bytes(version);
+        if version != REPLAY_VERSION {
+            bail!("unsupported replay version {version} (expected {REPLAY_VERSION})");
+        }
+        let header = options().deserialize_from(&mut reader)?;
+        let mut frames = vec![];
+        loop {
+            match options().deserialize_from(&mut reader) {
+                Ok(frame) => frames.push(frame),
+                // end of the file, or the recording was interrupted in the middle of a frame
+                Err(e) if matches!(*e, bincode::ErrorKind::Io(ref io) if io.kind() == ErrorKind::UnexpectedEof) => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(Self { header, frames })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let header = ReplayHeader {
+            seed: 56,
+            tick_rate: 64.0,
+            start_time: 0,
+        };
+        let frames = vec![
+            ReplayFrame { tick: 0, events: vec![ReplayEvent::Connect(1)] },
+            ReplayFrame {
+                tick: 3,
+                events: vec![
+                    ReplayEvent::Movement { client_id: 1, input: MovementInput(0b0100) },
+                    ReplayEvent::Snapshot(Snapshot {
+                        players: vec![PlayerState { client_id: 1, name: "Player".to_string() }],
+                        snakes: vec![SnakeState {
+                            client_id: 1,
+                            tail: vec![(Vec2::new(0.0, 10.0), Direction::Up), (Vec2::ZERO, Direction::Up)],
+                            length: TailLength { current_size: 10.0, target_size: 10.0 },
+                            speed: 1.0,
+                            acceleration: 0.0,
+                            input: MovementInput::default(),
+                        }],
+                        food: vec![Vec2::new(5.0, 5.0)],
+                    }),
+                ],
+            },
+        ];
+        // unique file name, so that concurrent test runs do not write to the same file
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        let path = std::env::temp_dir().join(format!("test_round_trip_{}_{nanos}.replay", std::process::id()));
+        let mut writer = ReplayWriter::create(&path, &header).unwrap();
+        for frame in &frames {
+            writer.write_frame(frame).unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+
+        let replay = Replay::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replay.header, header);
+        assert_eq!(replay.frames, frames);
+    }
+
+    #[test]
+    fn test_movement_input() {
+        let mut action = ActionState::<PlayerMovement>::default();
+        action.press(&PlayerMovement::Left);
+        let input = MovementInput::from_action_state(&action);
+        assert_eq!(input, MovementInput(0b0100));
+
+        let mut other = ActionState::<PlayerMovement>::default();
+        other.press(&PlayerMovement::Up);
+        input.apply(&mut other);
+        assert!(other.pressed(&PlayerMovement::Left));
+        assert!(!other.pressed(&PlayerMovement::Up));
+    }
+}
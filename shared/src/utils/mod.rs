@@ -1,3 +1,3 @@
 pub(crate) mod query;
 pub mod geometry;
-pub(crate) mod rand;
\ No newline at end of file
+pub mod rand;
\ No newline at end of file
@@ -5,8 +5,14 @@ pub struct RandPlugin;
 
 pub const SEED: u64 = 56;
 
+/// Seed of the `GlobalRng`. Insert it before adding the `RandPlugin` to use another seed than `SEED`
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Seed(pub u64);
+
 impl Plugin for RandPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(GlobalRng::with_seed(SEED));
+        let seed = app.world.get_resource::<Seed>().map_or(SEED, |seed| seed.0);
+        app.insert_resource(Seed(seed));
+        app.insert_resource(GlobalRng::with_seed(seed));
     }
-}
\ No newline at end of file
+}